@@ -0,0 +1,42 @@
+//! Direct vs. FFT convolution of kill-time PMFs.
+//!
+//! Run with `cargo bench -p osrs_shared_functions --bench distribution`.
+use std::time::Instant;
+
+use osrs_shared_functions::*;
+
+/// A smooth, normalised PMF of the given support length.
+fn kill_time_pmf(len: usize) -> Vec<f64> {
+    let mid = len as f64 / 2.0;
+    let width = len as f64 / 6.0;
+    let raw: Vec<f64> = (0..len).map(|t| (-((t as f64 - mid) / width).powi(2)).exp()).collect();
+    let total: f64 = raw.iter().sum();
+    raw.iter().map(|p| p / total).collect()
+}
+
+fn main() {
+    println!("{:>8} {:>12} {:>12} {:>9} {:>12}", "support", "direct (ms)", "fft (ms)", "speedup", "max error");
+    for len in [256, 1024, 4096, 16384] {
+        let a = kill_time_pmf(len);
+        let b = kill_time_pmf(len / 2);
+
+        let start = Instant::now();
+        let direct = convolve_direct(&a, &b);
+        let direct_time = start.elapsed().as_secs_f64();
+
+        let start = Instant::now();
+        let fast = convolve_fft(&a, &b);
+        let fft_time = start.elapsed().as_secs_f64();
+
+        let max_error = direct.iter().zip(&fast).map(|(x, y)| (x - y).abs()).fold(0.0, f64::max);
+        assert!(max_error < 1e-12, "support {len}: fft error {max_error}");
+        println!(
+            "{:>8} {:>12.2} {:>12.2} {:>8.1}x {:>12.2e}",
+            len,
+            direct_time * 1000.0,
+            fft_time * 1000.0,
+            direct_time / fft_time,
+            max_error
+        );
+    }
+}
@@ -0,0 +1,55 @@
+//! Dense transition matrix vs. banded hit kernel.
+//!
+//! Run with `cargo bench -p osrs_shared_functions --bench markov`.
+use std::time::Instant;
+
+use osrs_shared_functions::*;
+
+const ATTACKS: usize = 200;
+
+fn bench_dense(hp: usize, max_hit: usize, accuracy: f64) -> (Vec<f64>, f64) {
+    let start = Instant::now();
+    let mat = build_transition_matrix(hp, max_hit, accuracy);
+    let mut state = vec![0.0; hp + 1];
+    state[hp] = 1.0;
+    for _ in 0..ATTACKS {
+        state = propagate_state(&state, &mat);
+    }
+    (state, start.elapsed().as_secs_f64())
+}
+
+fn bench_banded(hp: usize, max_hit: usize, accuracy: f64) -> (Vec<f64>, f64) {
+    let start = Instant::now();
+    let kernel = HitKernel::new(max_hit, accuracy);
+    let mut state = vec![0.0; hp + 1];
+    state[hp] = 1.0;
+    for _ in 0..ATTACKS {
+        state = kernel.step(&state);
+    }
+    (state, start.elapsed().as_secs_f64())
+}
+
+fn main() {
+    // (label, hp, max_hit, accuracy)
+    let cases = [
+        ("guardian", 375, 55, 0.85),
+        ("tekton", 1350, 60, 0.75),
+        ("olm head", 2400, 45, 0.8),
+        ("vasa", 3000, 70, 0.9),
+    ];
+    println!("{:<10} {:>6} {:>8} {:>12} {:>12} {:>9}", "case", "hp", "max_hit", "dense (ms)", "banded (ms)", "speedup");
+    for (label, hp, max_hit, accuracy) in cases {
+        let (dense_state, dense_time) = bench_dense(hp, max_hit, accuracy);
+        let (banded_state, banded_time) = bench_banded(hp, max_hit, accuracy);
+        assert_eq!(dense_state, banded_state, "{label}: banded kernel diverged from the dense matrix");
+        println!(
+            "{:<10} {:>6} {:>8} {:>12.2} {:>12.2} {:>8.1}x",
+            label,
+            hp,
+            max_hit,
+            dense_time * 1000.0,
+            banded_time * 1000.0,
+            dense_time / banded_time
+        );
+    }
+}
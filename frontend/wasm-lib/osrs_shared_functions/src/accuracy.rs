@@ -0,0 +1,183 @@
+use osrs_shared_types::*;
+
+/// A change to how an attack's hit chance is rolled, layered on top of the
+/// plain attack roll vs. defence roll comparison.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AccuracyModifier {
+    /// The attacker rolls twice and keeps the better roll (Osmumten's fang).
+    BestOfTwoAttackRolls,
+    /// The defender rolls twice and keeps the better roll.
+    DefenderDoubleRoll,
+    /// After a miss the next attack gets an extra attack roll (Confliction gauntlets).
+    /// Stacked with `BestOfTwoAttackRolls` that attack keeps the best of three
+    /// rolls, where before the gauntlets added nothing on top of Osmumten's fang.
+    RerollOnMiss,
+    /// Every attack hits regardless of rolls.
+    GuaranteedHit,
+}
+
+/// Modifiers granted by the selected weapon.
+const WEAPON_ACCURACY_MODIFIERS: &[(&str, AccuracyModifier)] = &[
+    ("Osmumten's fang", AccuracyModifier::BestOfTwoAttackRolls),
+];
+
+/// Modifiers granted by worn gear: (item name, modifier, only with a one-handed weapon).
+const GEAR_ACCURACY_MODIFIERS: &[(&str, AccuracyModifier, bool)] = &[
+    ("Confliction gauntlets", AccuracyModifier::RerollOnMiss, true),
+];
+
+/// Base attack/defence rolls plus the modifiers that apply to them.
+#[derive(Clone, Debug)]
+pub struct AccuracyModel {
+    pub max_attack_roll: u64,
+    pub max_defence_roll: u64,
+    pub modifiers: Vec<AccuracyModifier>,
+}
+
+impl AccuracyModel {
+    pub fn new(max_attack_roll: u64, max_defence_roll: u64) -> Self {
+        AccuracyModel {
+            max_attack_roll,
+            max_defence_roll,
+            modifiers: Vec::new(),
+        }
+    }
+
+    pub fn with_modifier(mut self, modifier: AccuracyModifier) -> Self {
+        self.modifiers.push(modifier);
+        self
+    }
+
+    pub fn with_modifiers(mut self, modifiers: &[AccuracyModifier]) -> Self {
+        self.modifiers.extend_from_slice(modifiers);
+        self
+    }
+
+    fn has(&self, modifier: AccuracyModifier) -> bool {
+        self.modifiers.contains(&modifier)
+    }
+
+    /// Long-run probability that an attack hits.
+    pub fn hit_chance(&self) -> f64 {
+        if self.has(AccuracyModifier::GuaranteedHit) {
+            return 1.0;
+        }
+        let attack_rolls = if self.has(AccuracyModifier::BestOfTwoAttackRolls) { 2 } else { 1 };
+        let defence_rolls = if self.has(AccuracyModifier::DefenderDoubleRoll) { 2 } else { 1 };
+        let pone = roll_hit_chance(self.max_attack_roll, self.max_defence_roll, attack_rolls, defence_rolls);
+        if !self.has(AccuracyModifier::RerollOnMiss) {
+            return pone;
+        }
+        // Two-state chain: normal attacks until a miss, then one boosted attack.
+        // The stationary hit rate is ptwo / (1 + ptwo - pone).
+        let ptwo = roll_hit_chance(self.max_attack_roll, self.max_defence_roll, attack_rolls + 1, defence_rolls);
+        ptwo / (1.0 + ptwo - pone)
+    }
+}
+
+/// P(best of `attack_rolls` uniform draws on [0, att] > best of `defence_rolls` uniform draws on [0, def]).
+pub fn roll_hit_chance(att: u64, def: u64, attack_rolls: u32, defence_rolls: u32) -> f64 {
+    let a = att as f64;
+    let d = def as f64;
+    match (attack_rolls, defence_rolls) {
+        (0, _) => 0.0,
+        (_, 0) => 1.0,
+        (1, 1) => {
+            if att > def {
+                1.0 - (d + 2.0) / (2.0 * (a + 1.0))
+            } else {
+                a / (2.0 * (d + 1.0))
+            }
+        }
+        (2, 1) => {
+            if att > def {
+                1.0 - ((d + 2.0) * (2.0 * d + 3.0)) / (6.0 * (a + 1.0).powf(2.0))
+            } else {
+                (a * (4.0 * a + 5.0)) / (6.0 * (a + 1.0) * (d + 1.0))
+            }
+        }
+        (1, 2) => {
+            if att > def {
+                (d * (2.0 * d + 1.0) / 6.0 + (a - d) * (d + 1.0)) / ((a + 1.0) * (d + 1.0))
+            } else {
+                (a * (2.0 * a + 1.0)) / (6.0 * (d + 1.0).powf(2.0))
+            }
+        }
+        (k, j) => {
+            // P(max attack = x) * P(max defence < x), summed over every attack value
+            let k = k as i32;
+            let j = j as i32;
+            let mut p = 0.0;
+            for x in 1..=att {
+                let x = x as f64;
+                let p_attack = ((x + 1.0) / (a + 1.0)).powi(k) - (x / (a + 1.0)).powi(k);
+                let p_defence_below = (x.min(d + 1.0) / (d + 1.0)).powi(j);
+                p += p_attack * p_defence_below;
+            }
+            p
+        }
+    }
+}
+
+/// Collect the accuracy modifiers declared for a weapon and the gear worn with it.
+pub fn accuracy_modifiers_for(weapon: &SelectedItem, gear_items: &[Option<SelectedItem>]) -> Vec<AccuracyModifier> {
+    let mut modifiers = Vec::new();
+    for (name, modifier) in WEAPON_ACCURACY_MODIFIERS {
+        if weapon.name == *name {
+            modifiers.push(*modifier);
+        }
+    }
+    for (name, modifier, one_handed_only) in GEAR_ACCURACY_MODIFIERS {
+        if *one_handed_only && weapon.two_handed {
+            continue;
+        }
+        if gear_items.iter().flatten().any(|item| item.name == *name) {
+            modifiers.push(*modifier);
+        }
+    }
+    modifiers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// P(best attack roll > best defence roll), counting every pair of rolls.
+    fn enumerated_hit_chance(att: u64, def: u64, attack_rolls: u32, defence_rolls: u32) -> f64 {
+        let p_best_below = |x: u64, max: u64, rolls: u32| (x.min(max + 1) as f64 / (max + 1) as f64).powi(rolls as i32);
+        (0..=att)
+            .map(|x| (p_best_below(x + 1, att, attack_rolls) - p_best_below(x, att, attack_rolls)) * p_best_below(x, def, defence_rolls))
+            .sum()
+    }
+
+    #[test]
+    fn closed_forms_match_enumeration() {
+        for (att, def) in [(0, 0), (5, 9), (9, 5), (10, 10), (1234, 987), (987, 1234)] {
+            for (attack_rolls, defence_rolls) in [(1, 1), (2, 1), (1, 2), (3, 1), (2, 2)] {
+                let closed = roll_hit_chance(att, def, attack_rolls, defence_rolls);
+                let enumerated = enumerated_hit_chance(att, def, attack_rolls, defence_rolls);
+                assert!((closed - enumerated).abs() < 1e-12, "({attack_rolls}, {defence_rolls}) at {att} vs {def}: {closed} != {enumerated}");
+            }
+        }
+    }
+
+    #[test]
+    fn defender_double_roll_uses_one_two_form() {
+        let model = AccuracyModel::new(20_000, 15_000).with_modifier(AccuracyModifier::DefenderDoubleRoll);
+        let (a, d) = (20_000.0, 15_000.0);
+        let expected = (d * (2.0 * d + 1.0) / 6.0 + (a - d) * (d + 1.0)) / ((a + 1.0) * (d + 1.0));
+        assert!((model.hit_chance() - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn fang_with_gauntlets_rerolls_best_of_three() {
+        let (att, def) = (15_000, 20_000);
+        let model = AccuracyModel::new(att, def)
+            .with_modifier(AccuracyModifier::BestOfTwoAttackRolls)
+            .with_modifier(AccuracyModifier::RerollOnMiss);
+        let pone = roll_hit_chance(att, def, 2, 1);
+        let pthree = roll_hit_chance(att, def, 3, 1);
+        assert!(pthree > pone);
+        assert!((model.hit_chance() - pthree / (1.0 + pthree - pone)).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,179 @@
+use osrs_shared_types::*;
+
+use crate::distribution::Cdf;
+use crate::{calculate_accuracy_for_style, find_best_combat_style, salve_applies, wears_slayer_helm};
+
+/// Magic strength (%) added by the prayer assumed for magic (Augury).
+const MAGIC_PRAYER_STRENGTH: f64 = 4.0;
+
+/// Magic strength (%) added by a Salve amulet(ei) against the undead.
+const SALVE_MAGIC_STRENGTH: f64 = 20.0;
+
+/// Magic strength (%) added by a Slayer helmet (i).
+const SLAYER_MAGIC_STRENGTH: f64 = 15.0;
+
+/// Weapon category that only casts its own built-in spell, never Ancient Magicks.
+const POWERED_STAFF_CATEGORY: &str = "Powered Staff";
+
+/// An attack that hits every stacked monster within its area, up to a cap.
+#[derive(Clone, Copy, Debug)]
+pub struct AoeAttack {
+    pub name: &'static str,
+    pub combat_type: &'static str,
+    /// Most monsters one attack can hit (the game's multi-combat cap).
+    pub max_targets: usize,
+    /// Spell base damage; None uses the weapon's own max hit.
+    pub base_max_hit: Option<u32>,
+    /// Spell cast speed; None uses the weapon's speed.
+    pub attack_speed: Option<usize>,
+}
+
+/// Ancient Magicks bursts and barrages, and chinchompas.
+pub const AOE_ATTACKS: &[AoeAttack] = &[
+    AoeAttack { name: "Smoke burst", combat_type: "magic", max_targets: 9, base_max_hit: Some(13), attack_speed: Some(5) },
+    AoeAttack { name: "Shadow burst", combat_type: "magic", max_targets: 9, base_max_hit: Some(17), attack_speed: Some(5) },
+    AoeAttack { name: "Blood burst", combat_type: "magic", max_targets: 9, base_max_hit: Some(21), attack_speed: Some(5) },
+    AoeAttack { name: "Ice burst", combat_type: "magic", max_targets: 9, base_max_hit: Some(22), attack_speed: Some(5) },
+    AoeAttack { name: "Smoke barrage", combat_type: "magic", max_targets: 9, base_max_hit: Some(27), attack_speed: Some(5) },
+    AoeAttack { name: "Shadow barrage", combat_type: "magic", max_targets: 9, base_max_hit: Some(28), attack_speed: Some(5) },
+    AoeAttack { name: "Blood barrage", combat_type: "magic", max_targets: 9, base_max_hit: Some(29), attack_speed: Some(5) },
+    AoeAttack { name: "Ice barrage", combat_type: "magic", max_targets: 9, base_max_hit: Some(30), attack_speed: Some(5) },
+    AoeAttack { name: "Chinchompa", combat_type: "ranged", max_targets: 11, base_max_hit: None, attack_speed: None },
+    AoeAttack { name: "Red chinchompa", combat_type: "ranged", max_targets: 11, base_max_hit: None, attack_speed: None },
+    AoeAttack { name: "Black chinchompa", combat_type: "ranged", max_targets: 11, base_max_hit: None, attack_speed: None },
+];
+
+/// The AoE attack the player can use: the named spell when given, otherwise
+/// chinchompas on the ranged weapon (None without them).
+///
+/// A spell needs a magic weapon that can cast Ancient Magicks: powered staves
+/// such as Tumeken's shadow only cast their own spell, so naming an unknown
+/// spell or casting one without such a weapon is an error.
+pub fn aoe_attack_for(player: &Player, spell: Option<&str>) -> Result<Option<&'static AoeAttack>, String> {
+    let Some(spell) = spell else {
+        let Some(weapon) = player.gear_sets.ranged.selected_weapon.as_ref() else {
+            return Ok(None);
+        };
+        return Ok(AOE_ATTACKS.iter().find(|attack| attack.name.eq_ignore_ascii_case(&weapon.name)));
+    };
+    let attack = AOE_ATTACKS
+        .iter()
+        .find(|attack| attack.combat_type == "magic" && attack.name.eq_ignore_ascii_case(spell))
+        .ok_or_else(|| format!("unknown AoE spell \"{}\"", spell))?;
+    match player.gear_sets.mage.selected_weapon.as_ref() {
+        None => Err(format!("casting {} needs a magic weapon", attack.name)),
+        Some(weapon) if weapon.category == POWERED_STAFF_CATEGORY => Err(format!("{} cannot cast {}", weapon.name, attack.name)),
+        Some(_) => Ok(Some(attack)),
+    }
+}
+
+/// The player's style for `attack` against one of its targets; accuracy is
+/// rolled against each target's own defence.
+pub fn aoe_style(player: &Player, monster: &Monster, attack: &AoeAttack) -> Option<StyleResult> {
+    let Some(base_max_hit) = attack.base_max_hit else {
+        return Some(find_best_combat_style(player, monster, vec![attack.combat_type.to_string()]));
+    };
+    let gear_set = &player.gear_sets.mage;
+    let style = gear_set
+        .selected_weapon
+        .as_ref()?
+        .weapon_styles
+        .as_ref()?
+        .iter()
+        .find(|style| style.attack_type.eq_ignore_ascii_case("magic"))?;
+    let (accuracy, effective_attack, max_attack_roll, max_defence_roll) =
+        calculate_accuracy_for_style(player, monster, "magic", style, &gear_set.gear_stats);
+    let max_hit = spell_max_hit(base_max_hit, gear_set, monster);
+    let attack_speed = attack.attack_speed.unwrap_or(5);
+    Some(StyleResult {
+        combat_type: "magic".to_string(),
+        combat_style: attack.name.to_string(),
+        attack_type: style.attack_type.clone(),
+        max_hit,
+        accuracy,
+        effective_dps: max_hit as f64 * accuracy / attack_speed as f64,
+        effective_strength: 0,
+        effective_attack,
+        max_attack_roll,
+        max_defence_roll,
+        att_spd_reduction: 0,
+        attack_speed,
+    })
+}
+
+/// A spell's max hit: its base damage raised by the gear's magic strength,
+/// the magic prayer and any salve or slayer helmet bonus against `monster`,
+/// all adding up as they do for the weapon's own max hit.
+pub fn spell_max_hit(base_max_hit: u32, gear_set: &GearSetData, monster: &Monster) -> u32 {
+    let mut magic_strength = (gear_set.gear_stats.bonuses.magic_str as f64).min(100.0) + MAGIC_PRAYER_STRENGTH;
+    if salve_applies(gear_set, monster) {
+        magic_strength += SALVE_MAGIC_STRENGTH;
+    }
+    if wears_slayer_helm(gear_set) {
+        magic_strength += SLAYER_MAGIC_STRENGTH;
+    }
+    (base_max_hit as f64 * (1.0 + magic_strength / 100.0)).floor() as u32
+}
+
+/// Tick CDF of every target being dead, given each target's own kill CDF
+/// under the same attacks. Each target rolls accuracy and damage on its own,
+/// so the HP chains are independent and P(all dead by t) is their product.
+pub fn aoe_kill_cdf(target_kills: &[Cdf]) -> Cdf {
+    target_kills
+        .iter()
+        .fold(Cdf::new(vec![1.0]), |all_dead, kill| all_dead.max_of(kill))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+    use serde_json::json;
+
+    fn caster(weapon: &str, category: &str, worn: &[&str]) -> Player {
+        let mut player = test_fixtures::player("Dragon scimitar", 4, &[]);
+        player.gear_sets.mage.selected_weapon = Some(test_fixtures::weapon(weapon, category, 4, "Magic"));
+        player.gear_sets.mage.gear_items = worn.iter().map(|name| Some(test_fixtures::worn(name))).collect();
+        player
+    }
+
+    #[test]
+    fn spell_max_hit_adds_salve_and_slayer_bonuses() {
+        let monster = test_fixtures::monster("Skeleton", 50, json!({}));
+        let mut undead = monster.clone();
+        undead.attributes = Some(vec!["undead".to_string()]);
+        let max_hit = |worn: &[&str], monster: &Monster| spell_max_hit(30, &caster("Kodai wand", "Staff", worn).gear_sets.mage, monster);
+        // Augury alone: 30 * 1.04
+        assert_eq!(max_hit(&[], &undead), 31);
+        assert_eq!(max_hit(&["Salve amulet(ei)"], &undead), 37);
+        assert_eq!(max_hit(&["Salve amulet(ei)"], &monster), 31);
+        assert_eq!(max_hit(&["Slayer helmet (i)"], &monster), 35);
+        assert_eq!(max_hit(&["Salve amulet(ei)", "Slayer helmet (i)"], &undead), 41);
+    }
+
+    #[test]
+    fn spells_need_a_staff_that_casts_ancients() {
+        let staff = caster("Kodai wand", "Staff", &[]);
+        assert_eq!(aoe_attack_for(&staff, Some("ice barrage")).unwrap().map(|attack| attack.name), Some("Ice barrage"));
+        assert!(aoe_attack_for(&staff, Some("Fire surge")).unwrap_err().contains("unknown AoE spell"));
+
+        // Shadow's tripled accuracy and damage belong to its own spell only
+        let shadow = caster("Tumeken's shadow", "Powered Staff", &[]);
+        assert!(aoe_attack_for(&shadow, Some("Ice barrage")).unwrap_err().contains("Tumeken's shadow cannot cast"));
+
+        let no_staff = test_fixtures::player("Dragon scimitar", 4, &[]);
+        assert!(aoe_attack_for(&no_staff, Some("Ice barrage")).is_err());
+        assert!(aoe_attack_for(&no_staff, None).unwrap().is_none());
+    }
+
+    #[test]
+    fn aoe_kill_is_the_last_of_independent_kills() {
+        let fast = Cdf::new(vec![0.5, 1.0]);
+        let slow = Cdf::new(vec![0.0, 0.5, 1.0]);
+        assert_eq!(aoe_kill_cdf(&[fast.clone(), slow.clone()]).probs, vec![0.0, 0.5, 1.0]);
+        assert_eq!(aoe_kill_cdf(&[fast.clone(), fast.clone()]).probs, vec![0.25, 1.0]);
+        // Another target can only make the wave slower
+        let three = aoe_kill_cdf(&[fast.clone(), slow.clone(), Cdf::new(vec![0.0, 0.0, 0.5, 1.0])]);
+        assert!((0..4).all(|t| three.at(t) <= aoe_kill_cdf(&[fast.clone(), slow.clone()]).at(t)));
+    }
+}
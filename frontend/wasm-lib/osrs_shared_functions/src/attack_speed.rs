@@ -0,0 +1,53 @@
+use osrs_shared_types::*;
+
+/// Speed used when no weapon is equipped for the chosen style.
+pub const DEFAULT_ATTACK_SPEED: usize = 5;
+
+/// Autocasting through a non-powered staff always takes this many ticks.
+const AUTOCAST_SPEED: usize = 5;
+
+/// Weapons whose speed differs from their listed speed for a combat type:
+/// (weapon name, combat type, attack speed in ticks).
+const WEAPON_SPEED_OVERRIDES: &[(&str, &str, usize)] = &[
+    ("Eye of ayak", "magic", 3),
+    ("Harmonised nightmare staff", "magic", 4),
+];
+
+/// Ticks between attacks for `weapon` used with `style`.
+pub fn effective_attack_speed(weapon: &SelectedItem, combat_type: &str, style: &WeaponStyle) -> usize {
+    attack_speed_for(&weapon.name, &weapon.category, weapon.speed, combat_type, style.att_spd_reduction)
+}
+
+/// Ticks between attacks for a weapon with listed `speed`, less the style's
+/// `att_spd_reduction` (rapid styles carry 1). Magic through a regular staff
+/// uses the autocast speed instead.
+pub fn attack_speed_for(name: &str, category: &str, speed: i32, combat_type: &str, att_spd_reduction: i32) -> usize {
+    if let Some((_, _, speed)) = WEAPON_SPEED_OVERRIDES
+        .iter()
+        .find(|(weapon, override_type, _)| name == *weapon && combat_type == *override_type)
+    {
+        return *speed;
+    }
+
+    let speed = if combat_type == "magic" && category != "Powered Staff" { AUTOCAST_SPEED as i32 } else { speed };
+    (speed - att_spd_reduction).max(1) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reduction_comes_from_style_data() {
+        assert_eq!(attack_speed_for("Toxic blowpipe", "Thrown", 3, "ranged", 1), 2);
+        assert_eq!(attack_speed_for("Toxic blowpipe", "Thrown", 3, "ranged", 0), 3);
+    }
+
+    #[test]
+    fn magic_overrides_and_autocast() {
+        assert_eq!(attack_speed_for("Eye of ayak", "Powered Staff", 5, "magic", 0), 3);
+        assert_eq!(attack_speed_for("Harmonised nightmare staff", "Staff", 5, "magic", 0), 4);
+        assert_eq!(attack_speed_for("Kodai wand", "Staff", 4, "magic", 0), AUTOCAST_SPEED);
+        assert_eq!(attack_speed_for("Tumeken's shadow", "Powered Staff", 5, "magic", 0), 5);
+    }
+}
@@ -0,0 +1,241 @@
+use osrs_shared_types::*;
+
+use crate::{gear_set_for, monster_is_immune, SpecEffect, SpecialAttack};
+
+/// Ticks between venom or poison hitsplats (18 seconds).
+pub const TOXIN_INTERVAL: usize = 30;
+
+/// Venom starts at 6 damage and adds 2 per hitsplat up to 20.
+const VENOM_DAMAGE: (usize, usize, usize) = (6, 2, 20);
+
+/// Poison damage drops by 1 after every this many hitsplats.
+const POISON_HITS_PER_LEVEL: usize = 5;
+
+/// Burn deals 1 damage every 4 ticks, 10 times.
+const BURN_INTERVAL: usize = 4;
+const BURN_HITS: usize = 10;
+
+/// Gear that can envenom on any damaging hit: (name, chance).
+const VENOM_GEAR: &[(&str, f64)] = &[
+    ("Toxic blowpipe", 0.25),
+    ("Serpentine helm", 1.0 / 6.0),
+    ("Tanzanite helm", 1.0 / 6.0),
+    ("Magma helm", 1.0 / 6.0),
+];
+
+/// Weapon poison by name suffix: (suffix, starting damage); applied on 25% of damaging hits.
+const POISON_WEAPONS: &[(&str, usize)] = &[("(p++)", 6), ("(p+)", 5), ("(p)", 4)];
+const POISON_CHANCE: f64 = 0.25;
+
+/// An attacker whose damaging hits can apply a damage-over-time effect.
+#[derive(Clone, Copy, Debug)]
+pub struct DotApplier {
+    /// Index into the schedule's attackers.
+    pub attacker: usize,
+    /// Only hits from this weapon slot (see `Attacker::slot_hit`) apply it;
+    /// None for any slot.
+    pub slot: Option<usize>,
+    pub chance: f64,
+}
+
+impl DotApplier {
+    pub fn applies_to(&self, attacker: usize, slot: usize) -> bool {
+        self.attacker == attacker && self.slot.is_none_or(|own| own == slot)
+    }
+}
+
+/// Venom, poison or burn: once applied it deals `damage[0]`, `damage[1]`, ...
+/// every `interval` ticks, outside the attackers' hit cadence, until it wears off.
+///
+/// Hitsplats land on every `interval`-th tick of the fight rather than
+/// counting from the moment of application, and an effect already running
+/// is not re-applied (burn does not stack).
+#[derive(Clone, Debug)]
+pub struct DamageOverTime {
+    /// "venom", "poison" or "burn", as named in monster immunities.
+    pub kind: &'static str,
+    pub interval: usize,
+    pub damage: Vec<usize>,
+    /// Keep dealing the last entry of `damage` forever (venom never wears off).
+    pub persists: bool,
+    pub appliers: Vec<DotApplier>,
+}
+
+impl DamageOverTime {
+    pub fn venom() -> Self {
+        let (start, step, max) = VENOM_DAMAGE;
+        DamageOverTime {
+            kind: "venom",
+            interval: TOXIN_INTERVAL,
+            damage: (start..=max).step_by(step).collect(),
+            persists: true,
+            appliers: Vec::new(),
+        }
+    }
+
+    /// Poison starting at `damage`, wearing off after it reaches 1.
+    pub fn poison(damage: usize) -> Self {
+        DamageOverTime {
+            kind: "poison",
+            interval: TOXIN_INTERVAL,
+            damage: (1..=damage).rev().flat_map(|level| std::iter::repeat_n(level, POISON_HITS_PER_LEVEL)).collect(),
+            persists: false,
+            appliers: Vec::new(),
+        }
+    }
+
+    pub fn burn() -> Self {
+        DamageOverTime {
+            kind: "burn",
+            interval: BURN_INTERVAL,
+            damage: vec![1; BURN_HITS],
+            persists: false,
+            appliers: Vec::new(),
+        }
+    }
+
+    pub fn applied_by(mut self, attacker: usize, chance: f64) -> Self {
+        self.appliers.push(DotApplier { attacker, slot: None, chance });
+        self
+    }
+
+    /// Applied only by hits from one weapon slot of `attacker`, e.g. a spec.
+    pub fn applied_by_slot(mut self, attacker: usize, slot: usize, chance: f64) -> Self {
+        self.appliers.push(DotApplier { attacker, slot: Some(slot), chance });
+        self
+    }
+
+    /// Stage after stage `stage` has dealt its damage; None once it wears off.
+    pub fn next_stage(&self, stage: usize) -> Option<usize> {
+        if stage + 1 < self.damage.len() {
+            Some(stage + 1)
+        } else if self.persists {
+            Some(stage)
+        } else {
+            None
+        }
+    }
+
+    /// Whether it deals damage on `tick` of the fight.
+    pub fn ticks_on(&self, tick: usize) -> bool {
+        tick > 0 && tick.is_multiple_of(self.interval.max(1))
+    }
+
+    /// Chance of being applied by one damaging hit from `attacker`'s weapon `slot`.
+    pub fn chance_from(&self, attacker: usize, slot: usize) -> f64 {
+        let miss: f64 = self
+            .appliers
+            .iter()
+            .filter(|applier| applier.applies_to(attacker, slot))
+            .map(|applier| 1.0 - applier.chance)
+            .product();
+        1.0 - miss
+    }
+}
+
+/// Venom and poison from each party member's gear against `monster`;
+/// `styles[i]` is the combat type attacker i fights with. Burn only comes
+/// from special attacks (see `add_spec_burn`).
+///
+/// Appliers of the same effect share one instance, as the monster can only
+/// be venomed once. A venom-immune monster that can be poisoned takes the
+/// venom as 6-damage poison, and venom replaces weapon poison.
+pub fn gear_damage_over_time(party: &[Player], styles: &[&str], monster: &Monster) -> Vec<DamageOverTime> {
+    let mut venom = DamageOverTime::venom();
+    let mut poison_damage = 0;
+    let mut poison_appliers = Vec::new();
+    for (attacker, (player, &combat_type)) in party.iter().zip(styles).enumerate() {
+        let Some(gear_set) = gear_set_for(player, combat_type) else {
+            continue;
+        };
+        let mut worn: Vec<&str> = gear_set.gear_items.iter().flatten().map(|item| item.name.as_str()).collect();
+        if let Some(weapon) = gear_set.selected_weapon.as_ref().filter(|weapon| !worn.contains(&weapon.name.as_str())) {
+            worn.push(&weapon.name);
+        }
+        for item in worn {
+            if let Some(&(_, chance)) = VENOM_GEAR.iter().find(|(name, _)| item == *name) {
+                venom = venom.applied_by(attacker, chance);
+            }
+        }
+        let weapon_poison = gear_set
+            .selected_weapon
+            .as_ref()
+            .and_then(|weapon| POISON_WEAPONS.iter().find(|(suffix, _)| weapon.name.ends_with(suffix)));
+        if let Some(&(_, damage)) = weapon_poison {
+            poison_damage = poison_damage.max(damage);
+            poison_appliers.push(DotApplier { attacker, slot: None, chance: POISON_CHANCE });
+        }
+    }
+
+    toxin(monster, venom, poison_damage, poison_appliers).into_iter().collect()
+}
+
+/// Adds the burn `spec` can apply to `dots`, made from `attacker`'s weapon
+/// `slot`; another spec's burn already there is shared, as burn does not stack.
+pub fn add_spec_burn(dots: &mut Vec<DamageOverTime>, spec: &SpecialAttack, attacker: usize, slot: usize, monster: &Monster) {
+    let Some(chance) = spec.effects.iter().find_map(|effect| match effect {
+        SpecEffect::Burn(chance) => Some(*chance),
+        _ => None,
+    }) else {
+        return;
+    };
+    if monster_is_immune(monster, "burn") {
+        return;
+    }
+    match dots.iter_mut().find(|dot| dot.kind == "burn") {
+        Some(burn) => burn.appliers.push(DotApplier { attacker, slot: Some(slot), chance }),
+        None => dots.push(DamageOverTime::burn().applied_by_slot(attacker, slot, chance)),
+    }
+}
+
+/// The one venom or poison effect the monster can take, if any.
+fn toxin(monster: &Monster, venom: DamageOverTime, poison_damage: usize, poison_appliers: Vec<DotApplier>) -> Option<DamageOverTime> {
+    let poisonable = !monster_is_immune(monster, "poison");
+    if !venom.appliers.is_empty() {
+        if !monster_is_immune(monster, "venom") {
+            return Some(venom);
+        }
+        if poisonable {
+            let (start, ..) = VENOM_DAMAGE;
+            return Some(DamageOverTime { appliers: venom.appliers, ..DamageOverTime::poison(start) });
+        }
+    }
+    if poisonable && !poison_appliers.is_empty() {
+        return Some(DamageOverTime { appliers: poison_appliers, ..DamageOverTime::poison(poison_damage) });
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{special_attack_for, test_fixtures};
+    use serde_json::json;
+
+    #[test]
+    fn spec_burn_only_applies_from_the_spec_slot() {
+        let spec = special_attack_for("Burning claws").unwrap();
+        let mut dots = Vec::new();
+        add_spec_burn(&mut dots, spec, 0, 1, &test_fixtures::monster("Burnable", 100, json!({})));
+        assert_eq!(dots.len(), 1);
+        assert_eq!(dots[0].chance_from(0, 0), 0.0);
+        assert!((dots[0].chance_from(0, 1) - 0.15).abs() < 1e-12);
+        assert_eq!(dots[0].chance_from(1, 1), 0.0);
+
+        let mut dots = Vec::new();
+        add_spec_burn(&mut dots, spec, 0, 1, &test_fixtures::monster("Fireproof", 100, json!({ "burn": true })));
+        assert!(dots.is_empty());
+    }
+
+    #[test]
+    fn burning_claws_only_burn_with_their_spec() {
+        let party = [test_fixtures::player("Burning claws", 4, &[])];
+        let monster = test_fixtures::monster("Burnable", 100, json!({}));
+        let mut dots = gear_damage_over_time(&party, &["melee"], &monster);
+        assert!(dots.is_empty());
+        add_spec_burn(&mut dots, special_attack_for("Burning claws").unwrap(), 0, 1, &monster);
+        assert_eq!(dots.len(), 1);
+        assert!((dots[0].chance_from(0, 1) - 0.15).abs() < 1e-12);
+        assert_eq!(dots[0].chance_from(0, 0), 0.0);
+    }
+}
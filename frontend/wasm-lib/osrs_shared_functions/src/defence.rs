@@ -0,0 +1,234 @@
+use osrs_shared_types::*;
+
+use crate::accuracy::roll_hit_chance;
+use crate::distribution::convolve;
+
+/// Monster attack speed used when the monster data has none.
+pub const DEFAULT_MONSTER_ATTACK_SPEED: usize = 4;
+
+/// One of the attack styles a monster can use against the player.
+#[derive(Clone, Debug)]
+pub struct IncomingAttack {
+    pub style: String,
+    pub hit_chance: f64,
+    pub max_hit: u32,
+    pub prayer_blocked: bool,
+}
+
+/// Ticks between the monster's attacks.
+pub fn monster_attack_speed(monster: &Monster) -> usize {
+    monster.speed.map_or(DEFAULT_MONSTER_ATTACK_SPEED, |speed| speed as usize)
+}
+
+/// Which protection prayer covers an incoming attack style.
+fn protection_for_style(style: &str) -> &'static str {
+    match style {
+        "magic" => "magic",
+        "ranged" => "ranged",
+        _ => "melee",
+    }
+}
+
+/// Player defence roll against `style`, assuming an overload and a +25% defence prayer.
+pub fn player_defence_roll(player: &Player, gear: &GearStats, style: &str) -> u64 {
+    let potion_bonus = 21.0;
+    let prayer_bonus = 1.25;
+    let defence_level = player.combat_stats.defense as f64;
+    let effective_defence = ((defence_level + potion_bonus) * prayer_bonus).floor() + 8.0;
+
+    let (effective_level, defence_bonus) = match style {
+        "stab" => (effective_defence, gear.defensive.stab),
+        "slash" => (effective_defence, gear.defensive.slash),
+        "crush" => (effective_defence, gear.defensive.crush),
+        "ranged" => (effective_defence, gear.defensive.ranged),
+        "magic" => {
+            let magic_level = player.combat_stats.magic as f64;
+            let effective_magic = ((magic_level + potion_bonus) * prayer_bonus).floor();
+            ((0.7 * effective_magic + 0.3 * effective_defence).floor(), gear.defensive.magic)
+        }
+        _ => (effective_defence, 0),
+    };
+    (effective_level as u64) * (defence_bonus + 64).max(0) as u64
+}
+
+/// Monster attack roll for `style`.
+pub fn monster_attack_roll(monster: &Monster, style: &str) -> u64 {
+    let (level, bonus) = match style {
+        "magic" => (monster.skills.magic, monster.offensive.magic),
+        "ranged" => (monster.skills.ranged, monster.offensive.ranged),
+        _ => (monster.skills.atk, monster.offensive.atk),
+    };
+    (level + 9) as u64 * (bonus + 64).max(0) as u64
+}
+
+/// The attacks `monster` can make against the player wearing `gear_set`.
+/// "Melee" is treated as crush; "None" means the monster does not attack.
+pub fn incoming_attacks(
+    player: &Player,
+    monster: &Monster,
+    gear_set: &GearSetData,
+    protection_prayer: Option<&str>,
+) -> Vec<IncomingAttack> {
+    let max_hit = monster.max_hit.unwrap_or(0);
+    let styles = monster.style.clone().unwrap_or_default();
+    styles
+        .iter()
+        .map(|style| style.to_lowercase())
+        .filter(|style| style != "none")
+        .map(|style| {
+            let style = if style == "melee" { "crush".to_string() } else { style };
+            let attack_roll = monster_attack_roll(monster, &style);
+            let defence_roll = player_defence_roll(player, &gear_set.gear_stats, &style);
+            let prayer_blocked = protection_prayer.is_some_and(|prayer| prayer == protection_for_style(&style));
+            IncomingAttack {
+                hit_chance: roll_hit_chance(attack_roll, defence_roll, 1, 1),
+                max_hit,
+                prayer_blocked,
+                style,
+            }
+        })
+        .collect()
+}
+
+/// Damage PMF of a single monster attack, picking uniformly among its styles.
+pub fn incoming_hit_pmf(attacks: &[IncomingAttack]) -> Vec<f64> {
+    let max_hit = attacks.iter().map(|a| a.max_hit).max().unwrap_or(0) as usize;
+    let mut pmf = vec![0.0; max_hit + 1];
+    if attacks.is_empty() {
+        pmf[0] = 1.0;
+        return pmf;
+    }
+    let style_weight = 1.0 / attacks.len() as f64;
+    for attack in attacks {
+        if attack.prayer_blocked || attack.max_hit == 0 {
+            pmf[0] += style_weight;
+            continue;
+        }
+        let per_damage = attack.hit_chance / (attack.max_hit as f64 + 1.0);
+        pmf[0] += style_weight * (1.0 - attack.hit_chance);
+        for p in pmf.iter_mut().take(attack.max_hit as usize + 1) {
+            *p += style_weight * per_damage;
+        }
+    }
+    pmf
+}
+
+/// Damage taken while the player kills one monster.
+///
+/// `kill_times` is the per-tick death CDF for the monster, with tick 0 the
+/// start of the fight; the monster attacks every `monster_speed` ticks while
+/// alive. Mass beyond the last CDF entry is put on the final tick, so pass
+/// the CDF run out to (nearly) all of its mass rather than a capped one.
+pub fn damage_taken_pmf(hit_pmf: &[f64], monster_speed: usize, kill_times: &[f64]) -> Vec<f64> {
+    if kill_times.is_empty() {
+        return vec![1.0];
+    }
+    let mut out: Vec<f64> = Vec::new();
+    let mut after_hits = vec![1.0];
+    let mut hits_done = 0;
+    let mut prev_cdf = 0.0;
+    for (tick, &cdf) in kill_times.iter().enumerate() {
+        let p = if tick + 1 == kill_times.len() { 1.0 - prev_cdf } else { cdf - prev_cdf };
+        prev_cdf = cdf;
+        if p <= 0.0 { continue; }
+        let monster_hits = tick / monster_speed.max(1);
+        while hits_done < monster_hits {
+            after_hits = convolve(&after_hits, hit_pmf);
+            hits_done += 1;
+        }
+        if out.len() < after_hits.len() {
+            out.resize(after_hits.len(), 0.0);
+        }
+        for (o, &d) in out.iter_mut().zip(&after_hits) {
+            *o += p * d;
+        }
+    }
+    out
+}
+
+/// Damage taken across a whole room, treating each monster's fight as independent.
+pub fn room_damage_taken_pmf(monster_pmfs: &[Vec<f64>]) -> Vec<f64> {
+    monster_pmfs.iter().fold(vec![1.0], |acc, pmf| convolve(&acc, pmf))
+}
+
+/// Summarise a damage PMF against the player's hitpoints.
+pub fn summarize_damage_taken(pmf: &[f64], hitpoints: u32) -> DamageTaken {
+    let hp = hitpoints as usize;
+    let mut hp_lost_distribution = vec![0.0; hp + 1];
+    let mut expected_damage = 0.0;
+    for (damage, &p) in pmf.iter().enumerate() {
+        expected_damage += damage as f64 * p;
+        hp_lost_distribution[damage.min(hp)] += p;
+    }
+    let expected_hp_lost = hp_lost_distribution
+        .iter()
+        .enumerate()
+        .map(|(lost, &p)| lost as f64 * p)
+        .sum();
+    DamageTaken {
+        expected_damage,
+        expected_hp_lost,
+        p_death: hp_lost_distribution[hp],
+        hp_lost_distribution,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+    use serde_json::json;
+
+    fn attack(hit_chance: f64, max_hit: u32, prayer_blocked: bool) -> IncomingAttack {
+        IncomingAttack { style: "crush".to_string(), hit_chance, max_hit, prayer_blocked }
+    }
+
+    fn expected(pmf: &[f64]) -> f64 {
+        pmf.iter().enumerate().map(|(damage, &p)| damage as f64 * p).sum()
+    }
+
+    #[test]
+    fn defence_rolls_with_overload_and_prayer() {
+        let player = test_fixtures::player("Dragon scimitar", 4, &[]);
+        let gear = &player.gear_sets.melee.gear_stats;
+        // floor((99 + 21) * 1.25) + 8 = 158 effective defence, no defence bonus
+        assert_eq!(player_defence_roll(&player, gear, "slash"), 158 * 64);
+        // floor(0.7 * 150 + 0.3 * 158) = 152
+        assert_eq!(player_defence_roll(&player, gear, "magic"), 152 * 64);
+    }
+
+    #[test]
+    fn hit_pmf_picks_styles_evenly() {
+        let pmf = incoming_hit_pmf(&[attack(0.6, 20, false), attack(0.9, 30, true)]);
+        assert_eq!(pmf.len(), 31);
+        assert!((pmf.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        // The prayed-against style never damages
+        assert!((expected(&pmf) - 0.5 * 0.6 * 10.0).abs() < 1e-12);
+        assert!((pmf[0] - (0.5 + 0.5 * (0.4 + 0.6 / 21.0))).abs() < 1e-12);
+        assert_eq!(incoming_hit_pmf(&[]), vec![1.0]);
+    }
+
+    #[test]
+    fn incoming_attacks_from_monster_styles() {
+        let player = test_fixtures::player("Dragon scimitar", 4, &[]);
+        let monster = test_fixtures::monster("Hitter", 100, json!({}));
+        let attacks = incoming_attacks(&player, &monster, &player.gear_sets.melee, Some("melee"));
+        assert_eq!(attacks.len(), 1);
+        assert!(attacks[0].prayer_blocked);
+        let mut blocked = vec![0.0; 11];
+        blocked[0] = 1.0;
+        assert_eq!(incoming_hit_pmf(&attacks), blocked);
+    }
+
+    #[test]
+    fn damage_taken_counts_monster_attacks_while_alive() {
+        let hit = incoming_hit_pmf(&[attack(0.5, 10, false)]);
+        // Dead on tick 9 for sure, after the monster's attacks on ticks 4 and 8
+        let mut kill_times = vec![0.0; 10];
+        kill_times[9] = 1.0;
+        let pmf = damage_taken_pmf(&hit, 4, &kill_times);
+        assert!((pmf.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        assert!((expected(&pmf) - 2.0 * expected(&hit)).abs() < 1e-9);
+        assert_eq!(damage_taken_pmf(&hit, 4, &[]), vec![1.0]);
+    }
+}
@@ -0,0 +1,362 @@
+use std::f64::consts::PI;
+
+use osrs_shared_types::{Convergence, KillTimeSummary, QuantilePoint};
+
+/// Below this many multiply-adds a plain double loop beats the FFT.
+const DIRECT_CONVOLUTION_LIMIT: usize = 1 << 21;
+
+/// Probability mass function over a discrete support: `probs[t] = P(T = t)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pmf {
+    pub probs: Vec<f64>,
+}
+
+/// Cumulative distribution over the same support: `probs[t] = P(T <= t)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cdf {
+    pub probs: Vec<f64>,
+}
+
+impl Pmf {
+    pub fn new(probs: Vec<f64>) -> Self {
+        Pmf { probs }
+    }
+
+    /// All mass on `t`.
+    pub fn point(t: usize) -> Self {
+        let mut probs = vec![0.0; t + 1];
+        probs[t] = 1.0;
+        Pmf { probs }
+    }
+
+    pub fn from_cdf(cdf: &Cdf) -> Self {
+        let mut prev = 0.0;
+        let probs = cdf
+            .probs
+            .iter()
+            .map(|&c| {
+                let p = c - prev;
+                prev = c;
+                p
+            })
+            .collect();
+        Pmf { probs }
+    }
+
+    pub fn to_cdf(&self) -> Cdf {
+        let mut sum = 0.0;
+        let probs = self
+            .probs
+            .iter()
+            .map(|&p| {
+                sum += p;
+                sum
+            })
+            .collect();
+        Cdf { probs }
+    }
+
+    pub fn len(&self) -> usize {
+        self.probs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.probs.is_empty()
+    }
+
+    /// Total probability held by the support.
+    pub fn mass(&self) -> f64 {
+        self.probs.iter().sum()
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.probs.iter().enumerate().map(|(t, &p)| t as f64 * p).sum()
+    }
+
+    /// Distribution of X + Y for independent X ~ self, Y ~ other.
+    pub fn convolve(&self, other: &Pmf) -> Pmf {
+        Pmf { probs: convolve(&self.probs, &other.probs) }
+    }
+
+    /// Distribution of T + ticks.
+    pub fn shift(&self, ticks: usize) -> Pmf {
+        let mut probs = vec![0.0; ticks];
+        probs.extend_from_slice(&self.probs);
+        Pmf { probs }
+    }
+
+    pub fn quantile(&self, p: f64) -> Option<usize> {
+        self.to_cdf().quantile(p)
+    }
+
+    pub fn prob_by(&self, t: usize) -> f64 {
+        self.to_cdf().prob_by(t)
+    }
+
+    /// Distribution of max(X, Y) for independent X ~ self, Y ~ other.
+    pub fn max_of(&self, other: &Pmf) -> Pmf {
+        Pmf::from_cdf(&self.to_cdf().max_of(&other.to_cdf()))
+    }
+}
+
+impl Cdf {
+    pub fn new(probs: Vec<f64>) -> Self {
+        Cdf { probs }
+    }
+
+    /// P(T <= t), holding the last value past the end of the support.
+    pub fn at(&self, t: usize) -> f64 {
+        match self.probs.get(t) {
+            Some(&p) => p,
+            None => self.probs.last().copied().unwrap_or(0.0),
+        }
+    }
+
+    /// Per-tick CDF from (tick, P(T <= tick)) samples, holding each sample
+    /// until the next one.
+    pub fn from_samples(samples: &[(usize, f64)]) -> Self {
+        let len = samples.iter().map(|&(tick, _)| tick + 1).max().unwrap_or(0);
+        let mut probs = vec![0.0; len];
+        let mut current = 0.0;
+        let mut next = samples.iter().peekable();
+        for (t, p) in probs.iter_mut().enumerate() {
+            while let Some(&&(tick, prob)) = next.peek() {
+                if tick > t { break; }
+                current = prob;
+                next.next();
+            }
+            *p = current;
+        }
+        Cdf { probs }
+    }
+
+    /// E[T] = sum of P(T > t), ignoring any mass beyond the support.
+    pub fn mean(&self) -> f64 {
+        self.probs.iter().map(|&c| 1.0 - c).sum()
+    }
+
+    /// Var[T] from E[T^2] = sum of (2t + 1) P(T > t), ignoring any mass beyond the support.
+    pub fn variance(&self) -> f64 {
+        let second: f64 = self.probs.iter().enumerate().map(|(t, &c)| (2 * t + 1) as f64 * (1.0 - c)).sum();
+        let mean = self.mean();
+        second - mean * mean
+    }
+
+    /// Whether the CDF got to `cap`; the mass it never placed is the residual.
+    pub fn convergence(&self, cap: f64, killable: bool) -> Convergence {
+        let reached = self.probs.last().copied().unwrap_or(0.0);
+        Convergence {
+            killable,
+            truncated: reached < cap,
+            residual_mass: 1.0 - reached,
+        }
+    }
+
+    /// Largest gap between two CDFs over their supports (the Kolmogorov-Smirnov distance).
+    pub fn max_difference(&self, other: &Cdf) -> f64 {
+        let len = self.probs.len().max(other.probs.len());
+        (0..len).map(|t| (self.at(t) - other.at(t)).abs()).fold(0.0, f64::max)
+    }
+
+    /// Distribution of max(X, Y): the CDFs multiply.
+    pub fn max_of(&self, other: &Cdf) -> Cdf {
+        let len = self.probs.len().max(other.probs.len());
+        Cdf { probs: (0..len).map(|t| self.at(t) * other.at(t)).collect() }
+    }
+
+    /// P(T <= t).
+    pub fn prob_by(&self, t: usize) -> f64 {
+        self.at(t)
+    }
+
+    /// Smallest t with P(T <= t) >= p, or None if the support never reaches p.
+    pub fn quantile(&self, p: f64) -> Option<usize> {
+        self.probs.iter().position(|&c| c >= p)
+    }
+
+    /// p10/p50/p90/p99, any extra `quantiles` requested and, when a target is
+    /// given, P(T <= target).
+    pub fn summary(&self, target: Option<usize>, quantiles: &[f64]) -> KillTimeSummary {
+        KillTimeSummary {
+            p10: self.quantile(0.10),
+            p50: self.quantile(0.50),
+            p90: self.quantile(0.90),
+            p99: self.quantile(0.99),
+            quantiles: quantiles
+                .iter()
+                .map(|&p| QuantilePoint { p, tick: self.quantile(p) })
+                .collect(),
+            target,
+            p_within_target: target.map(|t| self.prob_by(t)),
+        }
+    }
+}
+
+/// Convolve two sequences, switching to an FFT once the direct loop gets long.
+pub fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    if a.len() * b.len() <= DIRECT_CONVOLUTION_LIMIT {
+        convolve_direct(a, b)
+    } else {
+        convolve_fft(a, b)
+    }
+}
+
+/// Both convolutions clamp at zero, so the path taken never changes the sign
+/// of a result: FFT round-off leaves tiny negative values where the true mass
+/// is zero.
+fn clamp_round_off(out: impl Iterator<Item = f64>) -> Vec<f64> {
+    out.map(|p| p.max(0.0)).collect()
+}
+
+pub fn convolve_direct(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut out = vec![0.0; a.len() + b.len() - 1];
+    for (i, &x) in a.iter().enumerate() {
+        if x == 0.0 { continue; }
+        for (o, &y) in out[i..].iter_mut().zip(b) {
+            *o += x * y;
+        }
+    }
+    clamp_round_off(out.into_iter())
+}
+
+pub fn convolve_fft(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let out_len = a.len() + b.len() - 1;
+    let size = out_len.next_power_of_two();
+    let mut fa: Vec<(f64, f64)> = a.iter().map(|&x| (x, 0.0)).collect();
+    let mut fb: Vec<(f64, f64)> = b.iter().map(|&x| (x, 0.0)).collect();
+    fa.resize(size, (0.0, 0.0));
+    fb.resize(size, (0.0, 0.0));
+    fft(&mut fa, false);
+    fft(&mut fb, false);
+    for (x, y) in fa.iter_mut().zip(&fb) {
+        *x = (x.0 * y.0 - x.1 * y.1, x.0 * y.1 + x.1 * y.0);
+    }
+    fft(&mut fa, true);
+    clamp_round_off(fa.iter().take(out_len).map(|&(re, _)| re / size as f64))
+}
+
+/// In-place iterative radix-2 FFT; `values.len()` must be a power of two.
+fn fft(values: &mut [(f64, f64)], inverse: bool) {
+    let n = values.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            values.swap(i, j);
+        }
+    }
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let twiddles: Vec<(f64, f64)> = (0..n / 2)
+        .map(|k| {
+            let (sin, cos) = (sign * 2.0 * PI * k as f64 / n as f64).sin_cos();
+            (cos, sin)
+        })
+        .collect();
+    let mut len = 2;
+    while len <= n {
+        let half = len / 2;
+        let stride = n / len;
+        for start in (0..n).step_by(len) {
+            for k in 0..half {
+                let (cos, sin) = twiddles[k * stride];
+                let u = values[start + k];
+                let v = values[start + k + half];
+                let t = (v.0 * cos - v.1 * sin, v.0 * sin + v.1 * cos);
+                values[start + k] = (u.0 + t.0, u.1 + t.1);
+                values[start + k + half] = (u.0 - t.0, u.1 - t.1);
+            }
+        }
+        len <<= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A lumpy PMF over `len` ticks with zeros in it, normalised.
+    fn lumpy(len: usize, seed: usize) -> Vec<f64> {
+        let raw: Vec<f64> = (0..len).map(|t| if (t + seed).is_multiple_of(3) { 0.0 } else { ((t * 7 + seed) % 11) as f64 }).collect();
+        let total: f64 = raw.iter().sum();
+        raw.into_iter().map(|p| p / total).collect()
+    }
+
+    fn assert_close(a: &[f64], b: &[f64]) {
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(b) {
+            assert!((x - y).abs() < 1e-12, "{} vs {}", x, y);
+        }
+    }
+
+    #[test]
+    fn fft_convolution_matches_direct_either_way_round() {
+        let (long, short) = (lumpy(300, 1), lumpy(17, 2));
+        let direct = convolve_direct(&long, &short);
+        assert_close(&convolve_fft(&long, &short), &direct);
+        assert_close(&convolve_fft(&short, &long), &direct);
+        assert_close(&convolve_direct(&short, &long), &direct);
+    }
+
+    #[test]
+    fn convolve_clamps_either_path_at_zero() {
+        // Both paths are used past the limit, and neither leaves negatives or loses mass
+        let (a, b) = (lumpy(1 << 11, 3), lumpy(1 << 11, 4));
+        assert!(a.len() * b.len() > DIRECT_CONVOLUTION_LIMIT);
+        let out = convolve(&a, &b);
+        assert!(out.iter().all(|&p| p >= 0.0));
+        assert!((out.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+
+        let round_off = [0.5, -1e-18, 0.5];
+        assert_eq!(convolve_direct(&[1.0], &round_off), vec![0.5, 0.0, 0.5]);
+        assert!(convolve_fft(&[1.0], &round_off).iter().all(|&p| p >= 0.0));
+    }
+
+    #[test]
+    fn shift_delays_every_outcome() {
+        let shifted = Pmf::new(vec![0.25, 0.75]).shift(3);
+        assert_eq!(shifted.probs, vec![0.0, 0.0, 0.0, 0.25, 0.75]);
+        assert!((shifted.mean() - (0.75 + 3.0)).abs() < 1e-12);
+        assert_eq!(Pmf::point(2).shift(0), Pmf::point(2));
+    }
+
+    #[test]
+    fn max_of_multiplies_the_cdfs() {
+        let a = Pmf::new(vec![0.5, 0.5]);
+        let b = Pmf::new(vec![0.0, 0.5, 0.5]);
+        // P(max <= 0) = 0, P(max <= 1) = 1 * 0.5, P(max <= 2) = 1
+        assert_close(&a.max_of(&b).probs, &[0.0, 0.5, 0.5]);
+        assert_close(&a.max_of(&a).probs, &[0.25, 0.75]);
+        assert_close(&a.to_cdf().max_of(&b.to_cdf()).probs, &[0.0, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn summary_reads_quantiles_and_the_target_off_the_cdf() {
+        let cdf = Cdf::new(vec![0.0, 0.1, 0.5, 0.9, 0.995]);
+        let summary = cdf.summary(Some(2), &[0.75, 0.999]);
+        assert_eq!((summary.p10, summary.p50, summary.p90, summary.p99), (Some(1), Some(2), Some(3), Some(4)));
+        assert_eq!(summary.quantiles.len(), 2);
+        assert_eq!((summary.quantiles[0].p, summary.quantiles[0].tick), (0.75, Some(3)));
+        assert_eq!(summary.target, Some(2));
+        assert_eq!(summary.p_within_target, Some(0.5));
+        // Past the end of the support the last value holds
+        assert_eq!(cdf.summary(Some(50), &[]).p_within_target, Some(0.995));
+    }
+
+    #[test]
+    fn summary_quantile_beyond_the_capped_mass_is_none() {
+        // The CDF stopped at the 0.9 cap: p99 and a 0.95 quantile were never reached
+        let summary = Cdf::new(vec![0.0, 0.4, 0.9]).summary(None, &[0.95]);
+        assert_eq!(summary.p90, Some(2));
+        assert_eq!(summary.p99, None);
+        assert_eq!(summary.quantiles[0].tick, None);
+        assert_eq!((summary.target, summary.p_within_target), (None, None));
+    }
+}
@@ -0,0 +1,108 @@
+use osrs_shared_types::*;
+
+use crate::distribution::Pmf;
+
+/// HP a monster restores on a schedule, optionally only within an HP phase.
+#[derive(Clone, Debug)]
+pub struct Heal {
+    /// `amount.probs[k]` = P(k HP restored) per heal.
+    pub amount: Pmf,
+    pub first_tick: usize,
+    /// Repeat every this many ticks; a single heal when None.
+    pub interval: Option<usize>,
+    pub last_tick: Option<usize>,
+    /// Only heals while the monster's HP is within [min, max].
+    pub hp_range: Option<(usize, usize)>,
+}
+
+impl Heal {
+    /// A single heal on `tick`.
+    pub fn at(tick: usize, amount: Pmf) -> Self {
+        Heal {
+            amount,
+            first_tick: tick,
+            interval: None,
+            last_tick: None,
+            hp_range: None,
+        }
+    }
+
+    /// A heal every `interval` ticks from `first_tick`.
+    pub fn every(interval: usize, first_tick: usize, amount: Pmf) -> Self {
+        Heal {
+            interval: Some(interval.max(1)),
+            ..Heal::at(first_tick, amount)
+        }
+    }
+
+    pub fn until(mut self, tick: usize) -> Self {
+        self.last_tick = Some(tick);
+        self
+    }
+
+    /// Only heal while HP is in [min_hp, max_hp], e.g. below a phase threshold.
+    pub fn during_phase(mut self, min_hp: usize, max_hp: usize) -> Self {
+        self.hp_range = Some((min_hp, max_hp));
+        self
+    }
+
+    /// Heal from monster data.
+    pub fn from_monster_heal(heal: &MonsterHeal) -> Self {
+        let base = match heal.interval {
+            Some(interval) => Heal::every(interval, heal.first_tick, Pmf::point(heal.amount as usize)),
+            None => Heal::at(heal.first_tick, Pmf::point(heal.amount as usize)),
+        };
+        let base = match heal.last_tick {
+            Some(tick) => base.until(tick),
+            None => base,
+        };
+        match (heal.min_hp, heal.max_hp) {
+            (None, None) => base,
+            (min_hp, max_hp) => base.during_phase(min_hp.unwrap_or(1) as usize, max_hp.map_or(usize::MAX, |hp| hp as usize)),
+        }
+    }
+
+    /// Ticks on which the heal happens, up to `max_tick`.
+    pub fn ticks(&self, max_tick: usize) -> Vec<usize> {
+        let last = self.last_tick.map_or(max_tick, |tick| tick.min(max_tick));
+        match self.interval {
+            Some(interval) => (self.first_tick..=last).step_by(interval).collect(),
+            None if self.first_tick <= last => vec![self.first_tick],
+            None => Vec::new(),
+        }
+    }
+
+    /// Whether the heal applies to a monster at `hp`.
+    pub fn in_phase(&self, hp: usize) -> bool {
+        self.hp_range.is_none_or(|(min, max)| min <= hp && hp <= max)
+    }
+
+    /// State vector after the heal; index 0 (dead) is absorbing and HP is
+    /// capped at the top of the state vector.
+    pub fn apply(&self, state: &[f64]) -> Vec<f64> {
+        let max_hp = state.len().saturating_sub(1);
+        let mut out = vec![0.0; state.len()];
+        if state.is_empty() {
+            return out;
+        }
+        out[0] = state[0];
+        for (hp, &s) in state.iter().enumerate().skip(1) {
+            if s == 0.0 {
+                continue;
+            }
+            if !self.in_phase(hp) {
+                out[hp] += s;
+                continue;
+            }
+            for (k, &p) in self.amount.probs.iter().enumerate() {
+                out[(hp + k).min(max_hp)] += s * p;
+            }
+        }
+        out
+    }
+}
+
+/// Heals declared in the monster data.
+pub fn monster_heals(monster: &Monster) -> Vec<Heal> {
+    monster.heals.iter().map(Heal::from_monster_heal).collect()
+}
@@ -0,0 +1,61 @@
+use osrs_shared_types::*;
+
+/// Distance assumed when neither the monster nor the room specifies one.
+pub const DEFAULT_DISTANCE: u32 = 1;
+
+/// Distance in tiles between the player and `monster`: the monster's own
+/// value wins over the room default.
+pub fn attack_distance(room: &Room, monster: &Monster) -> u32 {
+    monster.distance.or(room.distance).unwrap_or(DEFAULT_DISTANCE)
+}
+
+/// Ticks between an attack being made and its hitsplat landing.
+///
+/// Melee lands on the next tick; projectiles travel further the longer the
+/// distance, with different speeds for spells, arrows/bolts and thrown weapons.
+pub fn hit_delay_ticks(combat_type: &str, weapon: &SelectedItem, distance: u32) -> usize {
+    hit_delay_for(combat_type, &weapon.name, &weapon.category, distance)
+}
+
+/// `hit_delay_ticks` from the weapon's name and category, for crates with
+/// their own item types.
+pub fn hit_delay_for(combat_type: &str, weapon_name: &str, category: &str, distance: u32) -> usize {
+    let distance = distance as usize;
+    match combat_type {
+        "magic" => 1 + (1 + distance) / 3,
+        "ranged" => match category {
+            "Thrown" => 1 + distance / 6,
+            _ if weapon_name.contains("ballista") => if distance <= 4 { 2 } else { 3 },
+            _ => 1 + (3 + distance) / 6,
+        },
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delays_by_distance() {
+        // (combat type, weapon, category, distance, delay)
+        let table = [
+            ("melee", "Abyssal whip", "Whip", 1, 1),
+            ("melee", "Abyssal whip", "Whip", 10, 1),
+            ("magic", "Trident of the swamp", "Powered Staff", 1, 1),
+            ("magic", "Trident of the swamp", "Powered Staff", 2, 2),
+            ("magic", "Trident of the swamp", "Powered Staff", 5, 3),
+            ("magic", "Trident of the swamp", "Powered Staff", 10, 4),
+            ("ranged", "Twisted bow", "Bow", 1, 1),
+            ("ranged", "Twisted bow", "Bow", 3, 2),
+            ("ranged", "Twisted bow", "Bow", 9, 3),
+            ("ranged", "Toxic blowpipe", "Thrown", 5, 1),
+            ("ranged", "Toxic blowpipe", "Thrown", 6, 2),
+            ("ranged", "Heavy ballista", "Crossbow", 4, 2),
+            ("ranged", "Heavy ballista", "Crossbow", 5, 3),
+        ];
+        for (combat_type, weapon, category, distance, delay) in table {
+            assert_eq!(hit_delay_for(combat_type, weapon, category, distance), delay, "{weapon} at {distance}");
+        }
+    }
+}
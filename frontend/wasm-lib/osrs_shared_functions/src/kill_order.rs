@@ -0,0 +1,144 @@
+use osrs_shared_types::{KillOrderConfig, KillOrderResult, KillWindowResult};
+
+use crate::distribution::{Cdf, Pmf};
+use crate::timing::{EncounterTimeline, TickModel};
+
+/// Rooms with more monsters than this are only evaluated in listed order
+/// (6 monsters is already 720 orders).
+pub const MAX_KILL_ORDER_MONSTERS: usize = 6;
+
+/// Probability a kill window must be met with when the config gives none.
+const DEFAULT_WINDOW_PROBABILITY: f64 = 0.9;
+
+/// One monster's kill as the order search sees it: the per-tick kill CDF from
+/// the first attack on it, and the weapon that lands the kill, which sets the
+/// hand-off to the next target.
+#[derive(Clone, Debug)]
+pub struct OrderedKill {
+    pub kill_cdf: Cdf,
+    pub expected_ticks: f64,
+    pub attack_speed: usize,
+    pub hit_delay: usize,
+}
+
+/// The order to kill `count` monsters in that minimises room time.
+///
+/// `kill(monster, fight_start)` gives a monster's kill when its first attack
+/// is on room tick `fight_start`, so helpers timed from the room start (a
+/// thrall) are placed correctly in every order. Room time is the expected
+/// completion tick, or the `config.quantile` of it, aligned like the room's
+/// encounter timeline. Orders meeting every kill window are preferred; ties
+/// keep the order closest to the listed one.
+pub fn best_kill_order(
+    count: usize,
+    tick_model: TickModel,
+    config: &KillOrderConfig,
+    mut kill: impl FnMut(usize, usize) -> OrderedKill,
+) -> KillOrderResult {
+    let mut order: Vec<usize> = (0..count).collect();
+    let listed = evaluate_order(&order, tick_model, config, &mut kill);
+    let listed_order_ticks = listed.room_ticks;
+    let mut best = listed;
+    let mut orders_evaluated = 1;
+    if count <= MAX_KILL_ORDER_MONSTERS {
+        while next_permutation(&mut order) {
+            let candidate = evaluate_order(&order, tick_model, config, &mut kill);
+            orders_evaluated += 1;
+            if candidate.beats(&best) {
+                best = candidate;
+            }
+        }
+    }
+    KillOrderResult {
+        feasible: best.feasible(),
+        order: best.order,
+        room_ticks: best.room_ticks,
+        listed_order_ticks,
+        orders_evaluated,
+        windows: best.windows,
+    }
+}
+
+/// Room time and kill windows for one candidate order.
+struct OrderOutcome {
+    order: Vec<usize>,
+    room_ticks: f64,
+    windows: Vec<KillWindowResult>,
+}
+
+impl OrderOutcome {
+    fn feasible(&self) -> bool {
+        self.windows.iter().all(|window| window.met)
+    }
+
+    fn beats(&self, other: &OrderOutcome) -> bool {
+        match (self.feasible(), other.feasible()) {
+            (true, false) => true,
+            (false, true) => false,
+            _ => self.room_ticks < other.room_ticks - 1e-9,
+        }
+    }
+}
+
+fn evaluate_order(
+    order: &[usize],
+    tick_model: TickModel,
+    config: &KillOrderConfig,
+    kill: &mut impl FnMut(usize, usize) -> OrderedKill,
+) -> OrderOutcome {
+    let mut timeline = EncounterTimeline::new(tick_model);
+    let mut kills = Vec::with_capacity(order.len());
+    for &monster in order {
+        let fight_start = timeline.next_fight_start().round() as usize;
+        let ordered = kill(monster, fight_start);
+        timeline.add_kill(&ordered.kill_cdf, ordered.expected_ticks, ordered.attack_speed, ordered.hit_delay);
+        kills.push(ordered);
+    }
+    let room_ticks = match config.quantile {
+        Some(q) => timeline.cdf().quantile(q).map_or(f64::INFINITY, |tick| tick as f64),
+        None => timeline.expected_ticks(),
+    };
+    let windows = config
+        .windows
+        .iter()
+        .map(|window| {
+            let monsters: Vec<usize> = if window.monsters.is_empty() {
+                (0..order.len()).collect()
+            } else {
+                window.monsters.iter().copied().filter(|&monster| monster < order.len()).collect()
+            };
+            let positions = || order.iter().enumerate().filter(|(_, monster)| monsters.contains(monster)).map(|(pos, _)| pos);
+            let probability = match (positions().min(), positions().max()) {
+                (Some(first), Some(last)) => death_gap(&kills[first..=last], tick_model).prob_by(window.within_ticks),
+                _ => 1.0,
+            };
+            KillWindowResult {
+                monsters,
+                within_ticks: window.within_ticks,
+                probability,
+                met: probability >= window.probability.unwrap_or(DEFAULT_WINDOW_PROBABILITY),
+            }
+        })
+        .collect();
+    OrderOutcome { order: order.to_vec(), room_ticks, windows }
+}
+
+/// Ticks from the first kill in `kills` landing to the last one landing:
+/// every hand-off in between plus every later kill.
+fn death_gap(kills: &[OrderedKill], tick_model: TickModel) -> Pmf {
+    kills.windows(2).fold(Pmf::point(0), |gap, pair| {
+        let handoff = tick_model.handoff_ticks(pair[0].attack_speed, pair[0].hit_delay);
+        gap.shift(handoff).convolve(&Pmf::from_cdf(&pair[1].kill_cdf))
+    })
+}
+
+/// Step `order` to the next lexicographic permutation; false once it was the last.
+fn next_permutation(order: &mut [usize]) -> bool {
+    let Some(pivot) = (1..order.len()).rev().find(|&i| order[i - 1] < order[i]).map(|i| i - 1) else {
+        return false;
+    };
+    let successor = (pivot + 1..order.len()).rev().find(|&i| order[i] > order[pivot]).unwrap();
+    order.swap(pivot, successor);
+    order[pivot + 1..].reverse();
+    true
+}
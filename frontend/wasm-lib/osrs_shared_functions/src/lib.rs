@@ -1,6 +1,8 @@
+#[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 use osrs_shared_types::*;
 
+#[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 extern "C" {
     // fn alert(s: &str);
@@ -8,9 +10,84 @@ extern "C" {
     fn log(s: &str);
 }
 
+/// Native builds (tests, benches) have no browser console to log to.
+#[cfg(not(target_arch = "wasm32"))]
+fn log(_s: &str) {}
+
 macro_rules! console_log {
     ($($t:tt)*) => (log(&format_args!($($t)*).to_string()))
 }
+
+pub mod accuracy;
+pub mod aoe;
+pub mod attack_speed;
+pub mod damage_over_time;
+pub mod defence;
+pub mod distribution;
+pub mod healing;
+pub mod hit_delay;
+pub mod kill_order;
+pub mod markov;
+pub mod montecarlo;
+pub mod reflect;
+pub mod room;
+pub mod schedule;
+pub mod special_attack;
+pub mod supplies;
+pub mod thrall;
+pub mod timing;
+#[cfg(test)]
+mod test_fixtures;
+pub use accuracy::*;
+pub use aoe::*;
+pub use attack_speed::*;
+pub use damage_over_time::*;
+pub use defence::*;
+pub use distribution::*;
+pub use healing::*;
+pub use hit_delay::*;
+pub use kill_order::*;
+pub use markov::*;
+pub use montecarlo::*;
+pub use reflect::*;
+pub use room::*;
+pub use schedule::*;
+pub use special_attack::*;
+pub use supplies::*;
+pub use thrall::*;
+pub use timing::*;
+
+/// The gear set worn for `combat_type`.
+pub fn gear_set_for<'a>(player: &'a Player, combat_type: &str) -> Option<&'a GearSetData> {
+    match combat_type {
+        "magic" => Some(&player.gear_sets.mage),
+        "ranged" => Some(&player.gear_sets.ranged),
+        "melee" => Some(&player.gear_sets.melee),
+        _ => None,
+    }
+}
+
+/// The weapon equipped in the gear set used for `combat_type`.
+pub fn selected_weapon_for<'a>(player: &'a Player, combat_type: &str) -> Option<&'a SelectedItem> {
+    gear_set_for(player, combat_type).and_then(|gear_set| gear_set.selected_weapon.as_ref())
+}
+
+/// Whether an item named `name` is worn in `gear_set`.
+fn wears(gear_set: &GearSetData, name: &str) -> bool {
+    gear_set.gear_items.iter().any(|item_opt| item_opt.as_ref().is_some_and(|item| item.name == name))
+}
+
+/// Whether a Salve amulet(ei) in `gear_set` boosts attacks on `monster`: it must be undead.
+pub fn salve_applies(gear_set: &GearSetData, monster: &Monster) -> bool {
+    wears(gear_set, "Salve amulet(ei)")
+        && monster.attributes.as_ref().is_some_and(|attributes| attributes.contains(&"undead".to_string()))
+}
+
+/// Whether a Slayer helmet (i) is worn in `gear_set`.
+pub fn wears_slayer_helm(gear_set: &GearSetData) -> bool {
+    wears(gear_set, "Slayer helmet (i)")
+}
+
 pub fn find_best_combat_style(player: &Player, monster: &Monster, combat_types: Vec<String>) -> StyleResult {
     let mut best_style: Option<StyleResult> = None;
     let mut best_dps = 0.0;
@@ -34,7 +111,8 @@ pub fn find_best_combat_style(player: &Player, monster: &Monster, combat_types:
                 for style in styles {
                     let (max_hit, _effective_level) = calculate_max_hit_for_style(player, monster, &combat_type, style, gear_stats);
                     let (accuracy, effective_level, max_attack_roll, max_defence_roll) = calculate_accuracy_for_style(player, monster, &combat_type, style, gear_stats);
-                    let effective_dps = (max_hit as f64 * accuracy) / (weapon.speed as f64 - style.att_spd_reduction as f64);
+                    let attack_speed = effective_attack_speed(weapon, &combat_type, style);
+                    let effective_dps = (max_hit as f64 * accuracy) / attack_speed as f64;
                     let effective_strength = 0; // Not used for mage/ranged
                     let effective_attack = effective_level;
                     console_log!(
@@ -54,6 +132,7 @@ pub fn find_best_combat_style(player: &Player, monster: &Monster, combat_types:
                         effective_dps
                     );
                     let style_result = StyleResult {
+                        combat_type: combat_type.clone(),
                         combat_style: style.combat_style.clone(),
                         attack_type: style.attack_type.clone(),
                         max_hit,
@@ -64,6 +143,7 @@ pub fn find_best_combat_style(player: &Player, monster: &Monster, combat_types:
                         max_attack_roll,
                         max_defence_roll,
                         att_spd_reduction: style.att_spd_reduction,
+                        attack_speed,
                     };
                     if effective_dps > best_dps {
                         best_dps = effective_dps;
@@ -134,20 +214,12 @@ pub fn calculate_max_hit_for_style(
     console_log!("Selected weapon: {} (combat type: {})", weapon.name, combat_type);
     console_log!("Weapon category: {}", weapon.category);
     let mut salve_bonus = 1.0;
-    if gear_set.gear_items.iter().any(|item_opt| {
-        item_opt.as_ref().map_or(false, |item| item.name == "Salve amulet(ei)")
-    }) {
-        if let Some(attributes) = &monster.attributes {
-            if attributes.contains(&"undead".to_string()) {
-                salve_bonus = 1.2;
-                console_log!("Salve amulet(ei) bonus applied, new salve_bonus: {}", salve_bonus);
-            }
-        }
+    if salve_applies(gear_set, monster) {
+        salve_bonus = 1.2;
+        console_log!("Salve amulet(ei) bonus applied, new salve_bonus: {}", salve_bonus);
     };
     let mut slayer_bonus = 1.0;
-    if gear_set.gear_items.iter().any(|item_opt| {
-        item_opt.as_ref().map_or(false, |item| item.name == "Slayer helmet (i)")
-    }) {
+    if wears_slayer_helm(gear_set) {
         slayer_bonus = 1.15;
         console_log!("Slayer helmet (i) bonus applied, new slayer_bonus: {}", slayer_bonus);
     };
@@ -272,28 +344,19 @@ pub fn calculate_max_rolls_for_style(
         let tbow_mult = tbow_mult.clamp(1.0, 1.4);
         max_attack_roll = (max_attack_roll as f64 * tbow_mult).floor() as u64;
     };
-    if gear_set.gear_items.iter().any(|item_opt| {
-        item_opt.as_ref().map_or(false, |item| item.name == "Salve amulet(ei)")
-    }) {
-        if let Some(attributes) = &monster.attributes {
-            if attributes.contains(&"undead".to_string()) {
-                max_attack_roll = (max_attack_roll as f64 * 1.2).floor() as u64;
-                console_log!("Salve amulet(ei) bonus applied, new max_attack_roll: {}", max_attack_roll);
-            }
-        }
+    if salve_applies(gear_set, monster) {
+        max_attack_roll = (max_attack_roll as f64 * 1.2).floor() as u64;
+        console_log!("Salve amulet(ei) bonus applied, new max_attack_roll: {}", max_attack_roll);
     };
-    if gear_set.gear_items.iter().any(|item_opt| {
-        item_opt.as_ref().map_or(false, |item| item.name == "Slayer helmet (i)")
-    }) {
+    if wears_slayer_helm(gear_set) {
         max_attack_roll = (max_attack_roll as f64 * 1.15).floor() as u64;
         console_log!("Slayer helmet (i) bonus applied, new max_attack_roll: {}", max_attack_roll);
     };
     console_log!("Monster def: {}, monster def bonus: {}", monster.skills.def, defence_bonus);
-    let max_defence_roll;
-    if combat_type == "magic" {
-        max_defence_roll = (monster.skills.magic + 9) as u64 * (defence_bonus + 64) as u64;
+    let max_defence_roll = if combat_type == "magic" {
+        (monster.skills.magic + 9) as u64 * (defence_bonus + 64) as u64
     } else {
-        max_defence_roll = (monster.skills.def + 9) as u64 * (defence_bonus + 64) as u64;
+        (monster.skills.def + 9) as u64 * (defence_bonus + 64) as u64
     };
     
 
@@ -302,7 +365,6 @@ pub fn calculate_max_rolls_for_style(
 
 pub fn calculate_accuracy_for_style(player: &Player, monster: &Monster, combat_type: &str, style: &WeaponStyle, gear: &GearStats) -> (f64, u32, u64, u64) {
     let (max_attack_roll, max_defence_roll) = calculate_max_rolls_for_style(player, monster, combat_type, style, gear);
-    let mut accuracy;
     let (selected_weapon, gear_items) = match combat_type {
         "magic" => (
             player.gear_sets.mage.selected_weapon.as_ref(),
@@ -319,38 +381,12 @@ pub fn calculate_accuracy_for_style(player: &Player, monster: &Monster, combat_t
         _ => (None, &vec![]),
     };
     let weapon = selected_weapon.unwrap();
-    let is_two_handed = weapon.two_handed;
-    if weapon.name == "Osmumten's fang" {
-        if max_attack_roll > max_defence_roll {
-            accuracy = 1.0 - (((max_defence_roll as f64 + 2.0) * (2.0 * max_defence_roll as f64 + 3.0)) / (6.0 * (max_attack_roll as f64 + 1.0).powf(2.0)));
-        } else {
-            accuracy = (max_attack_roll as f64 * (4.0 * max_attack_roll as f64 + 5.0)) / (6.0 * (max_attack_roll as f64 + 1.0) * (max_defence_roll as f64 + 1.0));
-        };
-    } else {
-        if max_attack_roll > max_defence_roll {
-            accuracy = 1.0 - ((max_defence_roll as f64 + 2.0) / (2.0 * (max_attack_roll as f64 + 1.0)));
-        } else {
-            accuracy = max_attack_roll as f64 / (2.0 * (max_defence_roll as f64 + 1.0));
-        };
-    };
+    let modifiers = accuracy_modifiers_for(weapon, gear_items);
+    let accuracy = AccuracyModel::new(max_attack_roll, max_defence_roll)
+        .with_modifiers(&modifiers)
+        .hit_chance();
 
-    // console_log!("Base accuracy: {:.2}%", accuracy * 100.0);
-    // console_log!("player gear items: {:?}", gear_items);
-    if gear_items.iter().any(|item_opt| {
-        item_opt.as_ref().map_or(false, |item| item.name == "Confliction gauntlets")
-    }) && !is_two_handed {
-        let pone = accuracy;
-        let ptwo = if max_attack_roll >= max_defence_roll {
-            1.0 - ((max_defence_roll + 2) as f64 * (2.0 * max_defence_roll as f64 + 3.0)) / (6.0 * (max_attack_roll as f64 + 1.0).powf(2.0))
-        } else {
-            (max_attack_roll as f64 * (4.0 * max_attack_roll as f64 + 5.0)) / (6.0 * (max_attack_roll as f64 + 1.0) * (max_defence_roll as f64 + 1.0))
-        };
-        // console_log!("Confliction gauntlets bonus applied pone: {}", pone);
-        // console_log!("Confliction gauntlets bonus applied ptwo: {}", ptwo);
-        accuracy = ptwo / (1.0 + ptwo - pone);
-    };
-    
     // console_log!("Final accuracy: {:.2}%", accuracy * 100.0);
 
     (accuracy, 0, max_attack_roll, max_defence_roll)
-}
\ No newline at end of file
+}
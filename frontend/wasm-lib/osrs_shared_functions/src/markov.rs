@@ -0,0 +1,409 @@
+use osrs_shared_types::{HpBand, Overkill};
+
+// --- Markov Matrix Helpers (Python-style, updated) ---
+pub fn build_transition_matrix(hp: usize, max_hit: usize, accuracy: f64) -> Vec<Vec<f64>> {
+    let n = hp + 1;
+    let mut mat = vec![vec![0.0; n]; n];
+    for (i, row) in mat.iter_mut().enumerate() {
+        if i == 0 {
+            row[i] = 1.0; // Absorbing state
+            continue;
+        }
+        // Probability of 0 damage: miss + hitting 0
+        let p_zero = (1.0 - accuracy) + accuracy / (max_hit as f64 + 1.0);
+        row[i] += p_zero;
+        // Probability of k damage (1 <= k <= min(i, max_hit))
+        for dmg in 1..=usize::min(max_hit, i) {
+            let next_hp = i.saturating_sub(dmg);
+            row[next_hp] += accuracy / (max_hit as f64 + 1.0);
+        }
+        // Overkill: if max_hit > i, add probability to state 0
+        if max_hit > i {
+            let overkill_prob = (max_hit - i) as f64 * (accuracy / (max_hit as f64 + 1.0));
+            row[0] += overkill_prob;
+        }
+    }
+    mat
+}
+
+pub fn propagate_state(state: &[f64], mat: &[Vec<f64>]) -> Vec<f64> {
+    let n = state.len();
+    let mut new_state = vec![0.0; n];
+    for (p, row) in state.iter().zip(mat) {
+        for (next, t) in new_state.iter_mut().zip(row) {
+            *next += p * t;
+        }
+    }
+    new_state
+}
+
+/// Banded form of `build_transition_matrix`: one attack's damage applied to
+/// an HP-state vector, with overkill clamped into the dead state.
+///
+/// Stepping is O(hp * max_hit) instead of O(hp²) and sums the same terms in the
+/// same order as the dense matrix, so results match it exactly.
+#[derive(Clone, Debug)]
+pub struct HitKernel {
+    /// damage[k]: P(an attack deals exactly k damage), for 0 <= k <= max_hit.
+    pub damage: Vec<f64>,
+    pub max_hit: usize,
+    /// dead[i]: P(an attack on a monster at i HP kills it), for 1 <= i <= max_hit.
+    dead: Vec<f64>,
+}
+
+/// Mean and variance of the number of attacks needed for a kill.
+#[derive(Clone, Copy, Debug)]
+pub struct HitsToKill {
+    pub mean: f64,
+    pub variance: f64,
+}
+
+impl HitKernel {
+    /// Uniform damage on [0, max_hit] on a hit.
+    pub fn new(max_hit: usize, accuracy: f64) -> Self {
+        let p_damage = accuracy / (max_hit as f64 + 1.0);
+        let p_zero = (1.0 - accuracy) + p_damage;
+        let mut damage = vec![p_damage; max_hit + 1];
+        damage[0] = p_zero;
+        let mut dead = vec![0.0; max_hit + 1];
+        for (i, d) in dead.iter_mut().enumerate().skip(1) {
+            *d += p_damage;
+            if max_hit > i {
+                *d += (max_hit - i) as f64 * p_damage;
+            }
+        }
+        HitKernel { damage, max_hit, dead }
+    }
+
+    /// Arbitrary per-attack damage PMF; `pmf[k]` = P(k damage).
+    pub fn from_pmf(pmf: &[f64]) -> Self {
+        let max_hit = pmf.len().saturating_sub(1);
+        let mut dead = vec![0.0; max_hit + 1];
+        let mut tail = 0.0;
+        for i in (1..=max_hit).rev() {
+            tail += pmf[i];
+            dead[i] = tail;
+        }
+        HitKernel { damage: pmf.to_vec(), max_hit, dead }
+    }
+
+    /// State vector after one more attack; index 0 is dead, index i is i HP left.
+    pub fn step(&self, state: &[f64]) -> Vec<f64> {
+        let n = state.len();
+        let mut new_state = vec![0.0; n];
+        if n == 0 {
+            return new_state;
+        }
+        new_state[0] = state[0];
+        for (s, d) in state.iter().zip(&self.dead).skip(1) {
+            new_state[0] += s * d;
+        }
+        for j in 1..n {
+            let mut p = state[j] * self.damage[0];
+            for (&s, &d) in state[j + 1..n.min(j + self.max_hit + 1)].iter().zip(&self.damage[1..]) {
+                p += s * d;
+            }
+            new_state[j] = p;
+        }
+        new_state
+    }
+
+    /// Whether an attack can ever do damage.
+    pub fn can_damage(&self) -> bool {
+        self.damage.iter().skip(1).any(|&p| p > 0.0)
+    }
+
+    /// Expected HP removed by one more attack from `state`; overkill is not counted.
+    pub fn expected_damage(&self, state: &[f64]) -> f64 {
+        state
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(hp, &s)| {
+                let dealt: f64 = self.damage.iter().enumerate().map(|(k, &p)| p * k.min(hp) as f64).sum();
+                s * dealt
+            })
+            .sum()
+    }
+
+    /// Add one attack's contribution from `state` to `overkill`: the chance it
+    /// is made within one max hit of death, and the rolls that kill.
+    pub fn accumulate_overkill(&self, state: &[f64], overkill: &mut Overkill) {
+        if overkill.final_hit_distribution.len() <= self.max_hit {
+            overkill.final_hit_distribution.resize(self.max_hit + 1, 0.0);
+        }
+        for (hp, &s) in state.iter().enumerate().skip(1).take(self.max_hit) {
+            if s == 0.0 {
+                continue;
+            }
+            overkill.expected_finishing_attacks += s;
+            for (k, &p) in self.damage.iter().enumerate().skip(hp) {
+                overkill.final_hit_distribution[k] += s * p;
+                overkill.expected_overkill += s * p * (k - hp) as f64;
+            }
+        }
+    }
+
+    /// Exact overkill statistics for a monster at `hp`, by the same
+    /// back-substitution as `hits_to_kill`: from h HP the killing roll is k
+    /// with probability P(k) [k >= h] + sum over d < h of P(d) F(h - d, k).
+    pub fn overkill(&self, hp: usize) -> Overkill {
+        let p_stay = self.damage[0];
+        if p_stay >= 1.0 || hp == 0 {
+            return Overkill::default();
+        }
+        let width = self.max_hit + 1;
+        let mut final_hit = vec![vec![0.0; width]; hp + 1];
+        let mut wasted = vec![0.0; hp + 1];
+        let mut finishing = vec![0.0; hp + 1];
+        for h in 1..=hp {
+            let mut dist = vec![0.0; width];
+            let mut waste = 0.0;
+            let mut finish = if h <= self.max_hit { 1.0 } else { 0.0 };
+            for (k, &p) in self.damage.iter().enumerate().skip(1) {
+                if k >= h {
+                    dist[k] += p;
+                    waste += p * (k - h) as f64;
+                } else {
+                    for (d, &f) in dist.iter_mut().zip(&final_hit[h - k]) {
+                        *d += p * f;
+                    }
+                    waste += p * wasted[h - k];
+                    finish += p * finishing[h - k];
+                }
+            }
+            let scale = 1.0 / (1.0 - p_stay);
+            final_hit[h] = dist.iter().map(|d| d * scale).collect();
+            wasted[h] = waste * scale;
+            finishing[h] = finish * scale;
+        }
+        Overkill {
+            expected_overkill: wasted[hp],
+            expected_finishing_attacks: finishing[hp],
+            final_hit_distribution: final_hit.swap_remove(hp),
+        }
+    }
+
+    /// Exact mean and variance of the attacks needed to kill a monster at `hp`.
+    ///
+    /// The chain only moves down in HP, so the expectations satisfy
+    /// E[h] = 1 + sum_k P(k) E[max(h - k, 0)] with E[0] = 0, which is solved by
+    /// back-substitution from h = 1 upwards; the second moment follows the same
+    /// recurrence. Infinite when the monster cannot be damaged.
+    pub fn hits_to_kill(&self, hp: usize) -> HitsToKill {
+        let p_stay = self.damage[0];
+        if p_stay >= 1.0 {
+            return HitsToKill { mean: f64::INFINITY, variance: f64::INFINITY };
+        }
+        let mut mean = vec![0.0; hp + 1];
+        let mut second = vec![0.0; hp + 1];
+        for h in 1..=hp {
+            let mut mean_sum = 0.0;
+            let mut second_sum = 0.0;
+            for (k, &p) in self.damage.iter().enumerate().skip(1) {
+                let next = h.saturating_sub(k);
+                mean_sum += p * mean[next];
+                second_sum += p * (2.0 * mean[next] + second[next]);
+            }
+            mean[h] = (1.0 + mean_sum) / (1.0 - p_stay);
+            second[h] = (1.0 + 2.0 * p_stay * mean[h] + second_sum) / (1.0 - p_stay);
+        }
+        HitsToKill {
+            mean: mean[hp],
+            variance: (second[hp] - mean[hp] * mean[hp]).max(0.0),
+        }
+    }
+}
+
+/// Kill-time CDF and expectations for one weapon against one monster.
+#[derive(Clone, Debug)]
+pub struct WeaponKillStats {
+    /// Per-tick CDF, capped at `cap` for plotting.
+    pub kill_times: Vec<f64>,
+    pub attack_speed: usize,
+    pub expected_hits: f64,
+    pub expected_ticks: f64,
+    pub overkill: Overkill,
+    /// Remaining HP at each tick of `kill_times`.
+    pub hp_bands: Vec<HpBand>,
+}
+
+/// Expected and p10/p50/p90 remaining HP for an HP state vector.
+pub fn hp_band(state: &[f64], tick: usize) -> HpBand {
+    let mut expected_hp = 0.0;
+    let mut cumulative = 0.0;
+    let (mut p10, mut p50, mut p90) = (None, None, None);
+    for (hp, &p) in state.iter().enumerate() {
+        expected_hp += hp as f64 * p;
+        cumulative += p;
+        for (quantile, level) in [(&mut p10, 0.1), (&mut p50, 0.5), (&mut p90, 0.9)] {
+            if quantile.is_none() && cumulative >= level {
+                *quantile = Some(hp);
+            }
+        }
+    }
+    let top = state.len().saturating_sub(1);
+    HpBand {
+        tick,
+        expected_hp,
+        p10: p10.unwrap_or(top),
+        p50: p50.unwrap_or(top),
+        p90: p90.unwrap_or(top),
+    }
+}
+
+/// Per-tick kill-time CDF (capped at `cap` for plotting) plus exact expectations.
+///
+/// Attack k is made on `start_tick + (k - 1) * attack_speed` and its hitsplat
+/// lands `hit_delay` ticks later; entry t of the CDF is P(the killing hitsplat
+/// has landed by tick t). `max_steps` bounds the number of ticks produced;
+/// when no attack can do damage the CDF stops at the first attack and the
+/// expectations are infinite.
+#[allow(clippy::too_many_arguments)]
+pub fn weapon_kill_times_markov_per_tick_with_delay(
+    hp: usize,
+    max_hit: usize,
+    accuracy: f64,
+    cap: f64,
+    max_steps: usize,
+    attack_speed: usize,
+    start_tick: usize, // <-- number of ticks to wait before first attack
+    hit_delay: usize,  // <-- ticks between an attack and its hitsplat landing
+) -> WeaponKillStats {
+    let n = hp + 1;
+    let kernel = HitKernel::new(max_hit, accuracy);
+    let mut state = vec![0.0; n];
+    state[hp] = 1.0; // Start at full HP (index = hp)
+    let mut kill_times = Vec::new();
+    let mut hp_bands = Vec::new();
+    let mut p_dead = state[0];
+    let mut attack_num = 0;
+    if hp > 0 && !kernel.can_damage() {
+        kill_times.resize(start_tick.min(max_steps), 0.0);
+        hp_bands = (0..kill_times.len()).map(|tick| hp_band(&state, tick)).collect();
+        return WeaponKillStats {
+            kill_times,
+            attack_speed,
+            expected_hits: f64::INFINITY,
+            expected_ticks: f64::INFINITY,
+            overkill: Overkill::default(),
+            hp_bands,
+        };
+    }
+
+    while p_dead < cap && kill_times.len() < max_steps {
+        let next_state = kernel.step(&state);
+        attack_num += 1;
+        let lands = start_tick + (attack_num - 1) * attack_speed + hit_delay;
+        while kill_times.len() < lands.min(max_steps) {
+            hp_bands.push(hp_band(&state, kill_times.len()));
+            kill_times.push(p_dead); // Nothing new has landed yet
+        }
+        state = next_state;
+        p_dead = state[0];
+        if kill_times.len() < max_steps {
+            hp_bands.push(hp_band(&state, kill_times.len()));
+            kill_times.push(p_dead);
+        }
+    }
+
+    let expected_hits = kernel.hits_to_kill(hp).mean;
+    let expected_ticks = start_tick as f64 + (expected_hits - 1.0) * attack_speed as f64 + hit_delay as f64;
+    WeaponKillStats {
+        kill_times,
+        attack_speed,
+        expected_hits,
+        expected_ticks,
+        overkill: kernel.overkill(hp),
+        hp_bands,
+    }
+}
+
+/// Variance in ticks of a kill made with `attack_speed`-tick attacks.
+pub fn kill_ticks_variance(hits: &HitsToKill, attack_speed: usize) -> f64 {
+    hits.variance * (attack_speed * attack_speed) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(hp: usize) -> Vec<f64> {
+        let mut state = vec![0.0; hp + 1];
+        state[hp] = 1.0;
+        state
+    }
+
+    #[test]
+    fn kernel_step_matches_dense_matrix() {
+        let (hp, max_hit, accuracy) = (40, 13, 0.63);
+        let kernel = HitKernel::new(max_hit, accuracy);
+        let matrix = build_transition_matrix(hp, max_hit, accuracy);
+        let (mut banded, mut dense) = (point(hp), point(hp));
+        for _ in 0..10 {
+            banded = kernel.step(&banded);
+            dense = propagate_state(&dense, &matrix);
+            for (b, d) in banded.iter().zip(&dense) {
+                assert!((b - d).abs() < 1e-15);
+            }
+        }
+    }
+
+    #[test]
+    fn uniform_kernel_matches_its_pmf() {
+        let uniform = HitKernel::new(9, 0.8);
+        let from_pmf = HitKernel::from_pmf(&uniform.damage);
+        let (mut a, mut b) = (point(30), point(30));
+        for _ in 0..8 {
+            a = uniform.step(&a);
+            b = from_pmf.step(&b);
+        }
+        for (x, y) in a.iter().zip(&b) {
+            assert!((x - y).abs() < 1e-14);
+        }
+    }
+
+    #[test]
+    fn hits_to_kill_matches_forward_iteration() {
+        let (hp, kernel) = (57, HitKernel::new(11, 0.7));
+        let exact = kernel.hits_to_kill(hp);
+        // E[N] = sum P(N > n) and E[N^2] = sum (2n + 1) P(N > n)
+        let (mut mean, mut second) = (0.0, 0.0);
+        let mut state = point(hp);
+        for n in 0..5_000 {
+            let alive = 1.0 - state[0];
+            mean += alive;
+            second += (2 * n + 1) as f64 * alive;
+            state = kernel.step(&state);
+        }
+        assert!((exact.mean - mean).abs() < 1e-9);
+        assert!((exact.variance - (second - mean * mean)).abs() < 1e-7);
+    }
+
+    #[test]
+    fn hits_to_kill_geometric_case() {
+        // One HP and a 1 max hit that always hits: each attack kills with chance 1/2
+        let hits = HitKernel::new(1, 1.0).hits_to_kill(1);
+        assert!((hits.mean - 2.0).abs() < 1e-12);
+        assert!((hits.variance - 2.0).abs() < 1e-12);
+        assert!(HitKernel::new(5, 0.0).hits_to_kill(10).mean.is_infinite());
+    }
+
+    #[test]
+    fn overkill_matches_forward_accumulation() {
+        let (hp, kernel) = (35, HitKernel::new(12, 0.75));
+        let exact = kernel.overkill(hp);
+        let mut forward = Overkill::default();
+        let mut state = point(hp);
+        for _ in 0..5_000 {
+            kernel.accumulate_overkill(&state, &mut forward);
+            state = kernel.step(&state);
+        }
+        assert!((exact.expected_overkill - forward.expected_overkill).abs() < 1e-9);
+        assert!((exact.expected_finishing_attacks - forward.expected_finishing_attacks).abs() < 1e-9);
+        for (e, f) in exact.final_hit_distribution.iter().zip(&forward.final_hit_distribution) {
+            assert!((e - f).abs() < 1e-9);
+        }
+        assert!((exact.final_hit_distribution.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,325 @@
+use crate::distribution::{Cdf, Pmf};
+use crate::reflect::{recoil_damage, vengeance_damage};
+use crate::schedule::AttackSchedule;
+
+/// z-score of the two-sided 95% interval reported around the empirical CDF.
+const Z_95: f64 = 1.959_963_984_540_054;
+
+/// Seeded xoshiro256** generator, kept in-crate so the wasm builds need no
+/// extra dependencies and runs repeat exactly for a given seed.
+#[derive(Clone, Debug)]
+pub struct SimRng {
+    state: [u64; 4],
+}
+
+impl SimRng {
+    /// Expand `seed` with SplitMix64 so nearby seeds give unrelated streams.
+    pub fn seed(seed: u64) -> Self {
+        let mut x = seed;
+        let mut state = [0u64; 4];
+        for word in state.iter_mut() {
+            x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = x;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            *word = z ^ (z >> 31);
+        }
+        SimRng { state }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    /// Uniform on [0, 1) with 53 bits of precision.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Draw a value from `pmf`; any mass missing from the PMF lands on its last entry.
+    pub fn sample(&mut self, pmf: &Pmf) -> usize {
+        let u = self.next_f64();
+        let mut sum = 0.0;
+        for (k, &p) in pmf.probs.iter().enumerate() {
+            sum += p;
+            if u < sum {
+                return k;
+            }
+        }
+        pmf.len().saturating_sub(1)
+    }
+}
+
+/// Empirical kill-time distribution from simulated fights.
+#[derive(Clone, Debug)]
+pub struct MonteCarloResult {
+    pub trials: usize,
+    pub seed: u64,
+    /// Fraction of trials dead by each tick.
+    pub cdf: Cdf,
+    /// Per-tick 95% Wilson score interval around `cdf`.
+    pub lower: Cdf,
+    pub upper: Cdf,
+    /// Mean kill tick over the trials that finished, and its standard error.
+    pub mean: f64,
+    pub mean_std_error: f64,
+    /// Trials still alive after `max_tick`.
+    pub unfinished: usize,
+}
+
+/// Wilson score interval for `successes` out of `trials`.
+fn wilson_interval(successes: usize, trials: usize) -> (f64, f64) {
+    if trials == 0 {
+        return (0.0, 1.0);
+    }
+    let n = trials as f64;
+    let p = successes as f64 / n;
+    let z2 = Z_95 * Z_95;
+    let centre = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+    let half = Z_95 * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / (1.0 + z2 / n);
+    ((centre - half).max(0.0), (centre + half).min(1.0))
+}
+
+impl AttackSchedule {
+    /// Simulate `trials` fights against a monster at `hp`, tick by tick, with
+    /// the same timings as `run`: within a tick, vengeance is cast first, then
+    /// damage over time lands, then hitsplats, then reflected damage, then heals.
+    /// Hits roll against the defence drained so far.
+    pub fn simulate(&self, hp: usize, trials: usize, seed: u64, max_tick: usize) -> MonteCarloResult {
+        // (tick, 0 = vengeance cast / 1 = damage over time / 2 = hit /
+        //  3 = monster hit / 4 = heal, source or monster hit index, weapon slot)
+        let mut events: Vec<(usize, u8, usize, usize)> = Vec::new();
+        if let Some(reflection) = &self.reflection {
+            events.extend((0..=max_tick).filter(|&tick| reflection.casts_on(tick)).map(|tick| (tick, 0, 0, 0)));
+            events.extend(reflection.hit_ticks(max_tick).map(|(tick, hit)| (tick, 3, hit, 0)));
+        }
+        for (idx, dot) in self.dots.iter().enumerate() {
+            events.extend((0..=max_tick).filter(|&tick| dot.ticks_on(tick)).map(|tick| (tick, 1, idx, 0)));
+        }
+        for (idx, attacker) in self.attackers.iter().enumerate() {
+            events.extend(attacker.hits(max_tick).into_iter().map(|(tick, slot)| (tick, 2, idx, slot)));
+        }
+        for (idx, heal) in self.heals.iter().enumerate() {
+            events.extend(heal.ticks(max_tick).into_iter().map(|tick| (tick, 4, idx, 0)));
+        }
+        events.sort();
+
+        let mut rng = SimRng::seed(seed);
+        let mut deaths = vec![0usize; max_tick + 1];
+        let mut unfinished = 0;
+        for _ in 0..trials {
+            let mut hp_left = hp;
+            let mut kill_tick = if hp == 0 { Some(0) } else { None };
+            let mut dot_stages: Vec<Option<usize>> = vec![None; self.dots.len()];
+            let mut vengeance_armed = false;
+            let mut drains = 0;
+            for &(tick, kind, idx, slot) in &events {
+                if kill_tick.is_some() {
+                    break;
+                }
+                if kind == 0 {
+                    vengeance_armed = true;
+                } else if kind == 3 {
+                    let reflection = self.reflection.as_ref().expect("monster hits come from a reflection");
+                    let damage = rng.sample(&reflection.monster_hit);
+                    if reflection.recoils(idx) {
+                        hp_left = hp_left.saturating_sub(recoil_damage(damage));
+                    }
+                    if vengeance_armed && damage > 0 {
+                        hp_left = hp_left.saturating_sub(vengeance_damage(damage));
+                        vengeance_armed = false;
+                    }
+                } else if kind == 1 {
+                    if let Some(stage) = dot_stages[idx] {
+                        let dot = &self.dots[idx];
+                        hp_left = hp_left.saturating_sub(dot.damage[stage]);
+                        dot_stages[idx] = dot.next_stage(stage);
+                    }
+                } else if kind == 2 {
+                    let damage = rng.sample(&self.hit_after_drains(drains, idx, slot).pmf);
+                    hp_left = hp_left.saturating_sub(damage);
+                    if damage > 0 && drains < self.max_drains() && self.drain.as_ref().is_some_and(|drain| drain.applies_to(idx, slot)) {
+                        drains += 1;
+                    }
+                    if damage > 0 {
+                        for (stage, dot) in dot_stages.iter_mut().zip(&self.dots) {
+                            for applier in dot.appliers.iter().filter(|applier| applier.applies_to(idx, slot)) {
+                                if stage.is_none() && rng.next_f64() < applier.chance {
+                                    *stage = Some(0);
+                                }
+                            }
+                        }
+                    }
+                } else if self.heals[idx].in_phase(hp_left) {
+                    hp_left = (hp_left + rng.sample(&self.heals[idx].amount)).min(hp);
+                }
+                if hp_left == 0 {
+                    kill_tick = Some(tick);
+                }
+            }
+            match kill_tick {
+                Some(tick) => deaths[tick] += 1,
+                None => unfinished += 1,
+            }
+        }
+
+        let finished = trials - unfinished;
+        let (mut sum, mut sum_sq) = (0.0, 0.0);
+        for (tick, &count) in deaths.iter().enumerate() {
+            sum += (tick * count) as f64;
+            sum_sq += (tick * tick * count) as f64;
+        }
+        let mean = if finished > 0 { sum / finished as f64 } else { f64::INFINITY };
+        let mean_std_error = if finished > 1 {
+            let variance = (sum_sq - finished as f64 * mean * mean) / (finished - 1) as f64;
+            (variance.max(0.0) / finished as f64).sqrt()
+        } else {
+            f64::INFINITY
+        };
+
+        let last = deaths.iter().rposition(|&count| count > 0).map_or(0, |t| t + 1);
+        let (mut cdf, mut lower, mut upper) = (Vec::new(), Vec::new(), Vec::new());
+        let mut dead = 0;
+        for &count in &deaths[..last] {
+            dead += count;
+            let (lo, hi) = wilson_interval(dead, trials);
+            cdf.push(dead as f64 / trials.max(1) as f64);
+            lower.push(lo);
+            upper.push(hi);
+        }
+        MonteCarloResult {
+            trials,
+            seed,
+            cdf: Cdf::new(cdf),
+            lower: Cdf::new(lower),
+            upper: Cdf::new(upper),
+            mean,
+            mean_std_error,
+            unfinished,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::damage_over_time::DamageOverTime;
+    use crate::healing::Heal;
+    use crate::reflect::Reflection;
+    use crate::schedule::{Attacker, DefenceDrain, HitDistribution, WeaponSwitch};
+    use crate::thrall::THRALL_ATTACK_SPEED;
+
+    const TRIALS: usize = 20_000;
+    const MAX_TICK: usize = 20_000;
+
+    fn weapon(max_hit: usize, accuracy: f64, attack_speed: usize, hit_delay: usize) -> Attacker {
+        Attacker::new("weapon", HitDistribution::uniform(max_hit, accuracy), attack_speed).with_hit_delay(hit_delay)
+    }
+
+    fn thrall() -> Attacker {
+        Attacker::new("thrall", HitDistribution::uniform(3, 1.0), THRALL_ATTACK_SPEED).with_first_attack_tick(2)
+    }
+
+    /// The Markov CDF of `schedule` sits within the DKW band of a Monte Carlo
+    /// run (failing with probability 1e-3) and the means within 5 standard
+    /// errors. Every scenario draws from its own seed.
+    fn cross_check(hp: usize, schedule: AttackSchedule, seed: u64) {
+        let exact = schedule.kill_cdf(hp, 1.0 - 1e-9, MAX_TICK);
+        let sim = schedule.simulate(hp, TRIALS, seed, MAX_TICK);
+        assert_eq!(sim.unfinished, 0);
+        let bound = ((2.0 / 1e-3_f64).ln() / (2.0 * TRIALS as f64)).sqrt();
+        let distance = exact.max_difference(&sim.cdf);
+        assert!(distance < bound, "KS distance {distance} exceeds {bound}");
+        assert!((exact.mean() - sim.mean).abs() < 5.0 * sim.mean_std_error, "means {} and {} disagree", exact.mean(), sim.mean);
+    }
+
+    #[test]
+    fn weapon_alone() {
+        cross_check(300, AttackSchedule::new().with_attacker(weapon(45, 0.7, 5, 1)), 0x9e11);
+    }
+
+    #[test]
+    fn weapon_and_thrall() {
+        cross_check(300, AttackSchedule::new().with_attacker(weapon(45, 0.7, 5, 1)).with_attacker(thrall()), 0x51d7);
+    }
+
+    #[test]
+    fn two_players_with_switch_and_downtime() {
+        let switch = WeaponSwitch { tick: 30, hit: HitDistribution::uniform(70, 0.6), attack_speed: 6, hit_delay: 1 };
+        let schedule = AttackSchedule::new()
+            .with_attacker(weapon(50, 0.8, 4, 2).with_downtime(20, 40))
+            .with_attacker(weapon(30, 0.9, 3, 1).with_switch(switch));
+        cross_check(450, schedule, 0x2c4a);
+    }
+
+    #[test]
+    fn heals_below_half() {
+        let schedule = AttackSchedule::new()
+            .with_attacker(weapon(25, 0.75, 4, 1))
+            .with_attacker(thrall())
+            .with_heal(Heal::every(8, 8, Pmf::point(10)).during_phase(1, 100));
+        cross_check(200, schedule, 0x7f03);
+    }
+
+    #[test]
+    fn blowpipe_venom() {
+        let schedule = AttackSchedule::new()
+            .with_attacker(weapon(25, 0.8, 2, 2))
+            .with_dot(DamageOverTime::venom().applied_by(0, 0.25));
+        cross_check(400, schedule, 0xb6e5);
+    }
+
+    #[test]
+    fn poison_and_burn() {
+        let schedule = AttackSchedule::new()
+            .with_attacker(weapon(30, 0.7, 4, 1))
+            .with_attacker(weapon(20, 0.9, 5, 1))
+            .with_dot(DamageOverTime::poison(6).applied_by(0, 0.25))
+            .with_dot(DamageOverTime::burn().applied_by(0, 0.3).applied_by(1, 0.3));
+        cross_check(250, schedule, 0x43c9);
+    }
+
+    #[test]
+    fn vengeance_and_recoil() {
+        let reflection = Reflection::new(HitDistribution::uniform(45, 0.6).pmf, 5).with_recoil(Some(40)).with_vengeance(3);
+        let schedule = AttackSchedule::new()
+            .with_attacker(weapon(40, 0.7, 5, 1))
+            .with_dot(DamageOverTime::venom().applied_by(0, 1.0 / 6.0))
+            .with_reflection(reflection);
+        cross_check(300, schedule, 0xe08d);
+    }
+
+    #[test]
+    fn same_seed_repeats() {
+        let schedule = AttackSchedule::new().with_attacker(weapon(20, 0.6, 4, 1));
+        let (a, b) = (schedule.simulate(100, 500, 7, 2_000), schedule.simulate(100, 500, 7, 2_000));
+        assert_eq!(a.cdf, b.cdf);
+    }
+
+    #[test]
+    fn defence_drained_by_specs() {
+        // Two specs from slot 1, then the main weapon again from slot 2; the
+        // thrall keeps its own hit however far defence drops
+        let weapon_hits = |accuracy: f64, spec_accuracy: f64| {
+            vec![HitDistribution::uniform(45, accuracy), HitDistribution::uniform(60, spec_accuracy), HitDistribution::uniform(45, accuracy)]
+        };
+        let drain = DefenceDrain { attacker: 0, slot: 1, drained: vec![vec![weapon_hits(0.7, 0.6)], vec![weapon_hits(0.8, 0.7)]] };
+        let reflection = Reflection::new(HitDistribution::uniform(30, 0.5).pmf, 4).with_vengeance(6);
+        let schedule = AttackSchedule::new()
+            .with_attacker(weapon(45, 0.6, 4, 1).with_specs(0, 2, HitDistribution::uniform(60, 0.5), 6, 1))
+            .with_attacker(thrall())
+            .with_dot(DamageOverTime::burn().applied_by_slot(0, 1, 0.5))
+            .with_reflection(reflection)
+            .with_drain(drain);
+        cross_check(300, schedule, 0x1d5a);
+    }
+}
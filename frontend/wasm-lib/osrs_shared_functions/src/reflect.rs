@@ -0,0 +1,128 @@
+use osrs_shared_types::*;
+
+use crate::defence::{incoming_attacks, incoming_hit_pmf, monster_attack_speed};
+use crate::distribution::Pmf;
+use crate::gear_set_for;
+
+/// Ticks between vengeance casts.
+pub const VENGEANCE_COOLDOWN: usize = 30;
+
+/// Recoil rings: (name, charges); None never runs out.
+const RECOIL_RINGS: &[(&str, Option<usize>)] = &[
+    ("Ring of recoil", Some(40)),
+    ("Ring of suffering (r)", None),
+    ("Ring of suffering (ri)", None),
+];
+
+/// Damage reflected onto the monster by recoil for a hit of `damage`.
+pub fn recoil_damage(damage: usize) -> usize {
+    if damage == 0 { 0 } else { damage / 10 + 1 }
+}
+
+/// Damage reflected onto the monster by vengeance for a hit of `damage`.
+pub fn vengeance_damage(damage: usize) -> usize {
+    damage * 3 / 4
+}
+
+/// Damage the monster takes back from its own attacks on the player.
+///
+/// The monster attacks every `attack_speed` ticks from `first_hit_tick`. Recoil
+/// reflects every damaging hit; vengeance is cast every `VENGEANCE_COOLDOWN`
+/// ticks from `vengeance_cast` and reflects the next damaging hit, a cast
+/// while it is still armed being wasted. A recoil ring with charges stops
+/// after the hit on which its expected recoil uses them up.
+#[derive(Clone, Debug)]
+pub struct Reflection {
+    /// `monster_hit.probs[k]` = P(one monster attack deals k to the player).
+    pub monster_hit: Pmf,
+    pub attack_speed: usize,
+    pub first_hit_tick: usize,
+    /// Monster hits recoil applies to; None for no recoil ring.
+    pub recoil_hits: Option<usize>,
+    /// Fight tick of the first vengeance cast; None when vengeance is not used.
+    pub vengeance_cast: Option<usize>,
+    /// Attacker credited with the reflected damage: the player being hit.
+    pub owner: usize,
+}
+
+impl Reflection {
+    pub fn new(monster_hit: Pmf, attack_speed: usize) -> Self {
+        Reflection {
+            monster_hit,
+            attack_speed: attack_speed.max(1),
+            first_hit_tick: attack_speed.max(1),
+            recoil_hits: None,
+            vengeance_cast: None,
+            owner: 0,
+        }
+    }
+
+    /// Recoil with `charges` (None never runs out).
+    pub fn with_recoil(mut self, charges: Option<usize>) -> Self {
+        let per_hit: f64 = self.monster_hit.probs.iter().enumerate().map(|(damage, &p)| p * recoil_damage(damage) as f64).sum();
+        self.recoil_hits = Some(match charges {
+            Some(charges) if per_hit > 0.0 => (charges as f64 / per_hit).ceil() as usize,
+            _ => usize::MAX,
+        });
+        self
+    }
+
+    pub fn with_vengeance(mut self, first_cast: usize) -> Self {
+        self.vengeance_cast = Some(first_cast);
+        self
+    }
+
+    pub fn with_owner(mut self, owner: usize) -> Self {
+        self.owner = owner;
+        self
+    }
+
+    /// Whether any monster hit can reflect damage.
+    pub fn can_damage(&self) -> bool {
+        let damaging = self.monster_hit.probs.iter().skip(1).any(|&p| p > 0.0);
+        damaging && (self.recoil_hits.is_some_and(|hits| hits > 0) || self.vengeance_cast.is_some())
+    }
+
+    /// Ticks the monster's hits land on, with the hit's index, up to `max_tick`.
+    pub fn hit_ticks(&self, max_tick: usize) -> impl Iterator<Item = (usize, usize)> {
+        (self.first_hit_tick..=max_tick).step_by(self.attack_speed).enumerate().map(|(idx, tick)| (tick, idx))
+    }
+
+    /// Whether vengeance is cast on `tick`.
+    pub fn casts_on(&self, tick: usize) -> bool {
+        self.vengeance_cast.is_some_and(|first| tick >= first && (tick - first).is_multiple_of(VENGEANCE_COOLDOWN))
+    }
+
+    /// Whether the `hit`-th monster hit (from 0) still recoils.
+    pub fn recoils(&self, hit: usize) -> bool {
+        self.recoil_hits.is_some_and(|hits| hit < hits)
+    }
+}
+
+/// Recoil and vengeance for `player`, attacker `owner` of the schedule, being
+/// hit by `monster` while fighting in the `combat_type` gear set, starting on
+/// room tick `fight_start`; None when neither applies.
+pub fn reflection_for(config: &DPSConfig, player: &Player, owner: usize, monster: &Monster, combat_type: &str, fight_start: usize) -> Option<Reflection> {
+    let gear_set = gear_set_for(player, combat_type)?;
+    let recoil = RECOIL_RINGS
+        .iter()
+        .find(|(name, _)| gear_set.gear_items.iter().flatten().any(|item| item.name == *name))
+        .map(|&(_, charges)| charges);
+    // Next cast on the room's cooldown cycle, counted from this fight's start
+    let vengeance = config.vengeance.as_ref().map(|vengeance| match vengeance.cast_tick.checked_sub(fight_start) {
+        Some(first_cast) => first_cast,
+        None => (VENGEANCE_COOLDOWN - (fight_start - vengeance.cast_tick) % VENGEANCE_COOLDOWN) % VENGEANCE_COOLDOWN,
+    });
+    if recoil.is_none() && vengeance.is_none() {
+        return None;
+    }
+    let incoming = incoming_attacks(player, monster, gear_set, config.protection_prayer.as_deref());
+    let mut reflection = Reflection::new(Pmf::new(incoming_hit_pmf(&incoming)), monster_attack_speed(monster)).with_owner(owner);
+    if let Some(charges) = recoil {
+        reflection = reflection.with_recoil(charges);
+    }
+    if let Some(first_cast) = vengeance {
+        reflection = reflection.with_vengeance(first_cast);
+    }
+    Some(reflection)
+}
@@ -0,0 +1,866 @@
+use std::collections::HashMap;
+
+use osrs_shared_types::*;
+
+use crate::*;
+
+/// Probability mass simulated when expectations are taken from a tick CDF.
+const EXPECTATION_MASS: f64 = 1.0 - 1e-6;
+
+/// How a room is fought: which combat styles are considered and its tick layout.
+#[derive(Clone, Copy, Debug)]
+pub struct RoomRules {
+    pub combat_types: &'static [&'static str],
+    pub tick_model: TickModel,
+}
+
+/// Kill-time, damage-taken and supply results for every monster in `room`,
+/// killed one after another by the whole `party`: in listed order, or in the
+/// fastest order found when `config.kill_order` is set. A solo player may
+/// first kill stacked monsters together with an AoE attack (`config.aoe`)
+/// when that is faster.
+///
+/// Per-monster kill times are per-tick CDFs starting at the first attack on
+/// that monster, so each monster may be killed with a different weapon; the
+/// encounter CDF runs from the room start and is aligned to the room's tick cycle.
+/// The first player leads: monsters attack them, and their prayers, supplies
+/// and thrall are the ones reported. They also open every fight with the
+/// specs in `config.spec`, whose defence drain and burn carry into the rest
+/// of the fight.
+pub fn simulate_room(party: &[Player], room: &Room, config: &DPSConfig, rules: &RoomRules) -> serde_json::Value {
+    let combat_types: Vec<String> = rules.combat_types.iter().map(|t| t.to_string()).collect();
+    let player = &party[0];
+    let thrall = match configured_thrall(config, player) {
+        Ok(thrall) => thrall,
+        Err(e) => return serde_json::json!({ "error": format!("Failed to parse payload data: {}", e) }),
+    };
+    let tick_model = config
+        .retarget_delay
+        .map_or(rules.tick_model, |delay| rules.tick_model.with_retarget_delay(delay));
+
+    let mut encounter = EncounterTimeline::new(tick_model);
+    let mut tally = RoomTally::new(party.len());
+
+    // --- Stacked monsters killed together by an AoE attack ---
+    let aoe = config
+        .aoe
+        .as_ref()
+        .filter(|_| party.len() == 1)
+        .map(|aoe_config| AoeWave::new(player, room, config, &combat_types, tick_model, aoe_config))
+        .transpose();
+    let aoe = match aoe {
+        Ok(aoe) => aoe.flatten(),
+        Err(e) => return serde_json::json!({ "error": format!("Failed to parse payload data: {}", e) }),
+    };
+    let aoe_targets: Vec<usize> = match aoe.as_ref().filter(|wave| wave.result.chosen) {
+        Some(wave) => {
+            tally.add_aoe_wave(player, room, wave, config);
+            encounter.add_kill(&wave.kill.kill_cdf, wave.kill.expected_ticks, wave.kill.attack_speed, wave.kill.hit_delay);
+            wave.result.targets.clone()
+        }
+        None => Vec::new(),
+    };
+    let remaining: Vec<usize> = (0..room.monsters.len()).filter(|idx| !aoe_targets.contains(idx)).collect();
+
+    let kill_order = config.kill_order.as_ref().map(|order_config| {
+        // Windows are searched over the monsters left after any AoE wave
+        let order_config = KillOrderConfig {
+            windows: order_config
+                .windows
+                .iter()
+                .filter(|window| window.monsters.iter().all(|idx| !aoe_targets.contains(idx)))
+                .map(|window| KillWindowConfig {
+                    monsters: window.monsters.iter().filter_map(|idx| remaining.iter().position(|r| r == idx)).collect(),
+                    ..window.clone()
+                })
+                .collect(),
+            ..order_config.clone()
+        };
+        let search_model = TickModel { first_attack_tick: encounter.next_fight_start().round() as usize, ..tick_model };
+        // A kill depends on when its fight starts whenever something is timed
+        // from the room start: the thrall, the vengeance cooldown or the
+        // players' own schedules. Otherwise one fight per monster serves every order
+        let timed = config.thrall.is_some() || config.vengeance.is_some() || party.iter().any(|member| !member.schedule.switches.is_empty() || !member.schedule.downtime.is_empty());
+        let mut kills: HashMap<(usize, usize), OrderedKill> = HashMap::new();
+        let mut result = best_kill_order(remaining.len(), search_model, &order_config, |pos, fight_start| {
+            let key = (pos, if timed { fight_start } else { 0 });
+            kills
+                .entry(key)
+                .or_insert_with(|| MonsterFight::new(party, room, &room.monsters[remaining[pos]], config, &combat_types, thrall.as_ref(), fight_start).ordered_kill())
+                .clone()
+        });
+        result.order = result.order.iter().map(|&pos| remaining[pos]).collect();
+        for window in &mut result.windows {
+            window.monsters = window.monsters.iter().map(|&pos| remaining[pos]).collect();
+        }
+        result
+    });
+    let order: Vec<usize> = kill_order.as_ref().map_or_else(|| remaining.clone(), |result| result.order.clone());
+
+    for monster in order.iter().map(|&idx| &room.monsters[idx]) {
+        let fight_start = encounter.next_fight_start().round() as usize;
+        let fight = MonsterFight::new(party, room, monster, config, &combat_types, thrall.as_ref(), fight_start);
+        tally.add_fight(player, monster, &fight, config);
+        // --- Cumulative kill times for the encounter ---
+        let kill = fight.ordered_kill();
+        encounter.add_kill(&kill.kill_cdf, kill.expected_ticks, kill.attack_speed, kill.hit_delay);
+    }
+
+    let RoomTally { results, total_expected_hits, monster_expected_ticks, room_damage_pmfs, room_prayer_drain, mut room_damage_shares, all_killable, any_truncated } = tally;
+    let room_damage: f64 = room_damage_shares.iter().map(|share| share.expected_damage).sum();
+    for share in &mut room_damage_shares {
+        share.share = if room_damage > 0.0 { share.expected_damage / room_damage } else { 0.0 };
+    }
+
+    let total_expected_ticks = encounter.expected_ticks();
+    let encounter_cdf = encounter.cdf();
+    let encounter_ticks: Vec<(usize, f64)> = encounter_cdf.probs.iter().copied().enumerate().collect();
+    let encounter_kill_times_obj: Vec<serde_json::Value> = encounter_ticks.iter()
+        .map(|&(tick, prob)| {
+            serde_json::json!({
+                "tick": tick,
+                "probability": prob
+            })
+        })
+        .collect();
+
+    // --- Supplies for the whole room ---
+    let room_damage_pmf = room_damage_taken_pmf(&room_damage_pmfs);
+    let drain_per_tick = if monster_expected_ticks > 0.0 && monster_expected_ticks.is_finite() { room_prayer_drain / monster_expected_ticks } else { 0.0 };
+    let supply_needs = room_supply_needs(drain_per_tick, &room_damage_pmf, &encounter_ticks);
+    let supplies = supplies_for(player, &supply_needs);
+
+    serde_json::json!({
+        "results": results,
+        "total_hits": total_expected_hits,
+        "total_expected_ticks": total_expected_ticks,
+        "total_expected_seconds": total_expected_ticks * 0.6,
+        "encounter_kill_times": encounter_kill_times_obj,
+        "encounter_summary": encounter_cdf.summary(config.target_ticks, &config.quantiles),
+        "damage_taken": summarize_damage_taken(&room_damage_pmf, player.combat_stats.hitpoints),
+        "supplies": supplies,
+        "supply_needs": supply_needs,
+        "damage_share": room_damage_shares,
+        "kill_order": kill_order,
+        "aoe": aoe.map(|wave| wave.result),
+        "convergence": Convergence {
+            killable: all_killable,
+            truncated: any_truncated,
+            residual_mass: 1.0 - encounter_cdf.probs.last().copied().unwrap_or(0.0),
+        }
+    })
+}
+
+/// Per-monster results and the room totals built up from them.
+struct RoomTally {
+    results: Vec<serde_json::Value>,
+    total_expected_hits: f64,
+    monster_expected_ticks: f64,
+    room_damage_pmfs: Vec<Vec<f64>>,
+    room_prayer_drain: f64,
+    room_damage_shares: Vec<DamageShare>,
+    all_killable: bool,
+    any_truncated: bool,
+}
+
+impl RoomTally {
+    fn new(party_size: usize) -> Self {
+        RoomTally {
+            results: Vec::new(),
+            total_expected_hits: 0.0,
+            monster_expected_ticks: 0.0,
+            room_damage_pmfs: Vec::new(),
+            room_prayer_drain: 0.0,
+            room_damage_shares: (0..party_size)
+                .map(|player| DamageShare { player, expected_attacks: 0.0, expected_damage: 0.0, share: 0.0 })
+                .collect(),
+            all_killable: true,
+            any_truncated: false,
+        }
+    }
+
+    /// Record `fight`, with `player` tanking `monster` until it dies.
+    /// The wave's targets, each reported with its own fight, counting the
+    /// lead's casts once for the whole wave rather than once per target.
+    fn add_aoe_wave(&mut self, player: &Player, room: &Room, wave: &AoeWave, config: &DPSConfig) {
+        let (hits, attacks) = (self.total_expected_hits, self.room_damage_shares[0].expected_attacks);
+        for (&idx, fight) in wave.result.targets.iter().zip(&wave.fights) {
+            self.add_fight(player, &room.monsters[idx], fight, config);
+        }
+        self.total_expected_hits = hits + wave.result.expected_casts;
+        self.room_damage_shares[0].expected_attacks = attacks + wave.result.expected_casts;
+    }
+
+    fn add_fight(&mut self, player: &Player, monster: &Monster, fight: &MonsterFight, config: &DPSConfig) {
+        let protection_prayer = config.protection_prayer.as_deref();
+        let best_style = &fight.fighters[0].style;
+        let hp_distribution = config.hp_distribution_tick.map(|tick| fight.schedule.hp_distribution_at(monster.skills.hp as usize, tick));
+        let MonsterKill { kill_times, full_cdf, expected_hits, hits_variance, expected_ticks: expected_ttk, ticks_variance, damage_shares, convergence, overkill, hp_bands } = &fight.kill;
+        self.all_killable &= convergence.killable;
+        self.any_truncated |= convergence.truncated;
+        if !convergence.killable {
+            console_log!("Monster: {} cannot be killed with the selected gear", monster.name);
+        }
+        for (total, share) in self.room_damage_shares.iter_mut().zip(damage_shares) {
+            total.expected_attacks += share.expected_attacks;
+            total.expected_damage += share.expected_damage;
+        }
+        let kill_cdf = Cdf::new(kill_times.clone());
+        let kill_time_summary = kill_cdf.summary(config.target_ticks, &config.quantiles);
+
+        // --- Damage taken while this monster is alive, over the uncapped kill times ---
+        let incoming = gear_set_for(player, &best_style.combat_type)
+            .map(|gear_set| incoming_attacks(player, monster, gear_set, protection_prayer))
+            .unwrap_or_default();
+        let damage_pmf = damage_taken_pmf(&incoming_hit_pmf(&incoming), monster_attack_speed(monster), &full_cdf.probs);
+        let damage_taken = summarize_damage_taken(&damage_pmf, player.combat_stats.hitpoints);
+        self.room_damage_pmfs.push(damage_pmf);
+
+        let prayers = config
+            .active_prayers
+            .clone()
+            .unwrap_or_else(|| active_prayers(&best_style.combat_type, protection_prayer));
+        let prayer_bonus = gear_set_for(player, &best_style.combat_type)
+            .map_or(0, |gear_set| gear_set.gear_stats.bonuses.prayer);
+
+        self.total_expected_hits += expected_hits;
+        self.monster_expected_ticks += expected_ttk;
+        self.room_prayer_drain += expected_ttk * prayer_drain_per_tick(&prayers, prayer_bonus);
+
+        for (idx, fighter) in fight.fighters.iter().enumerate() {
+            console_log!("Monster: {}, Player: {}, Max Hit: {}, Accuracy: {:.4}, DPS: {:.4}", monster.name, idx, fighter.style.max_hit, fighter.style.accuracy, fighter.style.effective_dps);
+        }
+        self.results.push(serde_json::json!({
+            "monster_id": monster.id,
+            "monster_name": monster.name,
+            "expected_hits": expected_hits,
+            "expected_ticks": expected_ttk,
+            "expected_seconds": expected_ttk * 0.6,
+            "hits_variance": hits_variance,
+            "ticks_variance": ticks_variance,
+            "kill_times": kill_times,
+            "kill_time_summary": kill_time_summary,
+            "damage_taken": damage_taken,
+            "damage_share": damage_shares,
+            "convergence": convergence,
+            "overkill": overkill,
+            "hp_bands": hp_bands,
+            "hp_distribution": hp_distribution,
+            "damage_over_time": fight.schedule.dots.iter().map(|dot| dot.kind).collect::<Vec<_>>(),
+            "special_attack": fight.fighters[0].specs.as_ref().map(SpecPlan::summary),
+        }));
+    }
+}
+
+/// Stacked monsters hit together by one AoE attack, and how that compares
+/// with killing them one at a time. The lead player fights alone on both
+/// sides of the comparison, with gear damage over time and recoil but
+/// without the thrall, vengeance, special attacks or their own schedule.
+struct AoeWave {
+    result: AoeResult,
+    /// Each target's own fight against the AoE attack, in `result.targets` order.
+    fights: Vec<MonsterFight>,
+    /// The wave ends when the last target dies.
+    kill: OrderedKill,
+}
+
+impl AoeWave {
+    /// None when there is no AoE attack or fewer than two targets; an error
+    /// when the configured spell cannot be cast.
+    fn new(player: &Player, room: &Room, config: &DPSConfig, combat_types: &[String], tick_model: TickModel, aoe_config: &AoeConfig) -> Result<Option<Self>, String> {
+        let Some(attack) = aoe_attack_for(player, aoe_config.spell.as_deref())? else {
+            return Ok(None);
+        };
+        let targets: Vec<usize> = if aoe_config.targets.is_empty() {
+            (0..room.monsters.len()).collect()
+        } else {
+            aoe_config.targets.iter().copied().filter(|&idx| idx < room.monsters.len()).collect()
+        };
+        let targets: Vec<usize> = targets.into_iter().take(attack.max_targets).collect();
+        if targets.len() < 2 {
+            return Ok(None);
+        }
+
+        let mut fights = Vec::with_capacity(targets.len());
+        let mut full_kills = Vec::with_capacity(targets.len());
+        let mut killable = true;
+        for &idx in &targets {
+            let monster = &room.monsters[idx];
+            let Some(style) = aoe_style(player, monster, attack) else {
+                return Ok(None);
+            };
+            let fight = AoeWave::solo_fight(player, Fighter::with_style(player, style, attack_distance(room, monster)), monster, config);
+            full_kills.push(fight.kill.full_cdf.clone());
+            killable &= fight.kill.convergence.killable;
+            fights.push(fight);
+        }
+        let wave_cdf = aoe_kill_cdf(&full_kills);
+        let aoe_ticks = if killable { wave_cdf.mean() } else { f64::INFINITY };
+        // Every cast hits all the targets, so casts run until the last one dies
+        let expected_casts = if killable {
+            attacks_made(&fights[0].fighters[0].attacker(0), &wave_cdf, wave_cdf.probs.len()).0
+        } else {
+            f64::INFINITY
+        };
+        let cap_len = wave_cdf.quantile(config.cap).map_or(wave_cdf.probs.len(), |t| t + 1);
+
+        // The same monsters killed one after another from the first attack
+        let mut single_target = EncounterTimeline::new(TickModel::new(0).with_retarget_delay(tick_model.retarget_delay));
+        for &idx in &targets {
+            let monster = &room.monsters[idx];
+            let fighter = Fighter::new(player, monster, combat_types, attack_distance(room, monster));
+            let kill = AoeWave::solo_fight(player, fighter, monster, config).ordered_kill();
+            single_target.add_kill(&kill.kill_cdf, kill.expected_ticks, kill.attack_speed, kill.hit_delay);
+        }
+        let single_target_ticks = single_target.expected_ticks();
+
+        let kill = OrderedKill {
+            kill_cdf: Cdf::new(wave_cdf.probs[..cap_len].to_vec()),
+            expected_ticks: aoe_ticks,
+            attack_speed: fights[0].fighters[0].style.attack_speed,
+            hit_delay: fights[0].fighters[0].hit_delay,
+        };
+        Ok(Some(AoeWave {
+            result: AoeResult {
+                attack: attack.name.to_string(),
+                targets,
+                expected_casts,
+                aoe_ticks,
+                single_target_ticks,
+                chosen: aoe_ticks < single_target_ticks,
+            },
+            fights,
+            kill,
+        }))
+    }
+
+    /// `fighter` alone against `monster`, as both sides of the comparison are
+    /// fought. Every target recoils, but one vengeance cannot be spread over them.
+    fn solo_fight(player: &Player, fighter: Fighter, monster: &Monster, config: &DPSConfig) -> MonsterFight {
+        let combat_type = fighter.style.combat_type.clone();
+        let dots = gear_damage_over_time(std::slice::from_ref(player), &[combat_type.as_str()], monster);
+        let reflection = reflection_for(config, player, 0, monster, &combat_type, 0)
+            .map(|reflection| Reflection { vengeance_cast: None, ..reflection });
+        let effects = AttackSchedule { dots, reflection, ..AttackSchedule::new() };
+        MonsterFight::with_fighters(vec![fighter], None, effects, monster, config, 0)
+    }
+}
+
+/// The party's fight against one monster, first attacked on room tick `fight_start`.
+struct MonsterFight {
+    fighters: Vec<Fighter>,
+    schedule: AttackSchedule,
+    kill: MonsterKill,
+}
+
+impl MonsterFight {
+    fn new(party: &[Player], room: &Room, monster: &Monster, config: &DPSConfig, combat_types: &[String], thrall: Option<&Thrall>, fight_start: usize) -> Self {
+        let distance = attack_distance(room, monster);
+        let fighters = MonsterFight::fighters(party, monster, config, combat_types, distance, fight_start);
+        let thrall = thrall_for(thrall, monster);
+        let styles: Vec<&str> = fighters.iter().map(|fighter| fighter.style.combat_type.as_str()).collect();
+        let mut dots = gear_damage_over_time(party, &styles, monster);
+        if let (Some(plan), Some(slot)) = (&fighters[0].specs, fighters[0].spec_slot()) {
+            add_spec_burn(&mut dots, plan.evaluation.spec, 0, slot, monster);
+        }
+        // Monsters attack the lead player, whose recoil and vengeance reflect their hits
+        let reflection = reflection_for(config, &party[0], 0, monster, styles[0], fight_start);
+        let drain = defence_drain(party, &fighters[0], monster, config, combat_types, distance, fight_start);
+        let effects = AttackSchedule { dots, reflection, drain, ..AttackSchedule::new() };
+        MonsterFight::with_fighters(fighters, thrall, effects, monster, config, fight_start)
+    }
+
+    /// Every party member's fighter, the lead opening with any specs in `config`.
+    fn fighters(party: &[Player], monster: &Monster, config: &DPSConfig, combat_types: &[String], distance: u32, fight_start: usize) -> Vec<Fighter> {
+        let mut fighters: Vec<Fighter> = party
+            .iter()
+            .map(|member| Fighter::new(member, monster, combat_types, distance).with_schedule(member, monster, distance, fight_start))
+            .collect();
+        fighters[0].specs = config.spec.as_ref().and_then(|spec| SpecPlan::new(&party[0], monster, spec, &fighters[0].style.combat_type, distance));
+        fighters
+    }
+
+    /// `fighters` against `monster`, with `effects` holding the schedule's
+    /// damage over time, reflection and defence drain; the attackers and the
+    /// monster's heals are filled in here.
+    fn with_fighters(
+        fighters: Vec<Fighter>,
+        thrall: Option<Thrall>,
+        effects: AttackSchedule,
+        monster: &Monster,
+        config: &DPSConfig,
+        fight_start: usize,
+    ) -> Self {
+        let best_style = &fighters[0].style;
+
+        let max_hit = best_style.max_hit as usize;
+        let accuracy = best_style.accuracy;
+        let hp = monster.skills.hp as usize;
+        let attack_speed = best_style.attack_speed;
+        let hit_delay = fighters[0].hit_delay;
+
+        let max_ticks = config.max_ticks.unwrap_or(1000 * attack_speed);
+        let heals = monster_heals(monster);
+        let exact_chain = fighters.len() == 1
+            && fighters[0].on_cooldown()
+            && thrall.is_none()
+            && heals.is_empty()
+            && effects.dots.is_empty()
+            && effects.reflection.is_none()
+            && effects.drain.is_none();
+        let weapons = fighters.iter().enumerate().map(|(idx, fighter)| fighter.attacker(idx));
+        let thralls = thrall.map(|t| t.attackers(fight_start, max_ticks)).unwrap_or_default();
+        let schedule = AttackSchedule { attackers: weapons.chain(thralls).collect(), heals, ..effects };
+        let kill = if exact_chain {
+            weapon_kill(hp, max_hit, accuracy, config.cap, max_ticks, attack_speed, hit_delay)
+        } else {
+            scheduled_kill(&schedule, fighters.len(), hp, config.cap, max_ticks)
+        };
+        MonsterFight { fighters, schedule, kill }
+    }
+
+    fn ordered_kill(&self) -> OrderedKill {
+        OrderedKill {
+            kill_cdf: Cdf::new(self.kill.kill_times.clone()),
+            expected_ticks: self.kill.expected_ticks,
+            attack_speed: self.fighters[0].style.attack_speed,
+            hit_delay: self.fighters[0].hit_delay,
+        }
+    }
+}
+
+/// Defence drained by the `lead` fighter's specs: every party member's hits
+/// re-derived against the monster at each drained level, with the fighters
+/// built as in `MonsterFight::fighters`.
+fn defence_drain(
+    party: &[Player],
+    lead: &Fighter,
+    monster: &Monster,
+    config: &DPSConfig,
+    combat_types: &[String],
+    distance: u32,
+    fight_start: usize,
+) -> Option<DefenceDrain> {
+    let plan = lead.specs.as_ref()?;
+    let slot = lead.spec_slot()?;
+    let mut drained = Vec::with_capacity(plan.count);
+    let mut weakened = monster.clone();
+    for _ in 0..plan.count {
+        weakened.skills.def = plan.evaluation.spec.drained_defence(weakened.skills.def)?;
+        let fighters = MonsterFight::fighters(party, &weakened, config, combat_types, distance, fight_start);
+        let hits = fighters
+            .iter()
+            .enumerate()
+            .map(|(idx, fighter)| {
+                let attacker = fighter.attacker(idx);
+                (0..=attacker.switches.len()).map(|slot| attacker.slot_hit(slot).clone()).collect()
+            })
+            .collect();
+        drained.push(hits);
+    }
+    Some(DefenceDrain { attacker: 0, slot, drained })
+}
+
+/// Special attacks a fighter opens the fight with, made back to back.
+struct SpecPlan {
+    evaluation: SpecEvaluation,
+    count: usize,
+    hit_delay: usize,
+}
+
+impl SpecPlan {
+    /// The specs `config` asks for with the weapon of its gear set, or of
+    /// `combat_type` (the set fought with); None when there is no spec to make.
+    fn new(player: &Player, monster: &Monster, config: &SpecConfig, combat_type: &str, distance: u32) -> Option<Self> {
+        let combat_type = config.combat_type.as_deref().unwrap_or(combat_type);
+        let evaluation = best_spec(player, monster, combat_type)?;
+        let count = evaluation.spec.uses(config.energy.unwrap_or(100)) as usize;
+        let hit_delay = selected_weapon_for(player, combat_type).map_or(1, |weapon| hit_delay_ticks(combat_type, weapon, distance));
+        (count > 0).then_some(SpecPlan { evaluation, count, hit_delay })
+    }
+
+    fn summary(&self) -> serde_json::Value {
+        let SpecEvaluation { spec, accuracy, max_hit, .. } = &self.evaluation;
+        serde_json::json!({
+            "weapon": spec.weapon,
+            "name": spec.name,
+            "specs": self.count,
+            "accuracy": accuracy,
+            "max_hit": max_hit,
+            "expected_damage": self.evaluation.expected_damage(),
+            "expected_heal": self.evaluation.expected_heal(),
+        })
+    }
+}
+
+/// One party member's best style against a monster, when its hits land and
+/// the player's own schedule within the fight.
+struct Fighter {
+    style: StyleResult,
+    hit_delay: usize,
+    first_attack_tick: usize,
+    switches: Vec<WeaponSwitch>,
+    downtime: Vec<Downtime>,
+    specs: Option<SpecPlan>,
+}
+
+impl Fighter {
+    fn new(player: &Player, monster: &Monster, combat_types: &[String], distance: u32) -> Self {
+        let style = find_best_combat_style(player, monster, combat_types.to_vec());
+        Fighter::with_style(player, style, distance)
+    }
+
+    fn with_style(player: &Player, style: StyleResult, distance: u32) -> Self {
+        let hit_delay = selected_weapon_for(player, &style.combat_type)
+            .map_or(1, |weapon| hit_delay_ticks(&style.combat_type, weapon, distance));
+        Fighter { style, hit_delay, first_attack_tick: 0, switches: Vec::new(), downtime: Vec::new(), specs: None }
+    }
+
+    /// `player.schedule` moved onto the ticks of a fight first attacked on
+    /// room tick `fight_start`; a switch made before the fight starts it.
+    fn with_schedule(mut self, player: &Player, monster: &Monster, distance: u32, fight_start: usize) -> Self {
+        let schedule = &player.schedule;
+        self.first_attack_tick = schedule.first_attack_delay;
+        let mut switches: Vec<&GearSwitchConfig> = schedule.switches.iter().collect();
+        switches.sort_by_key(|switch| switch.tick);
+        self.switches = switches
+            .into_iter()
+            .map(|switch| {
+                let gear = Fighter::new(player, monster, std::slice::from_ref(&switch.combat_type), distance);
+                WeaponSwitch {
+                    tick: switch.tick.saturating_sub(fight_start),
+                    hit: gear.hit(),
+                    attack_speed: gear.style.attack_speed,
+                    hit_delay: gear.hit_delay,
+                }
+            })
+            .collect();
+        self.downtime = schedule
+            .downtime
+            .iter()
+            .filter(|window| window.end > fight_start)
+            .map(|window| Downtime { start: window.start.saturating_sub(fight_start), end: window.end - fight_start })
+            .collect();
+        self
+    }
+
+    /// Attacks on cooldown from the first tick with one weapon.
+    fn on_cooldown(&self) -> bool {
+        self.first_attack_tick == 0 && self.switches.is_empty() && self.downtime.is_empty() && self.specs.is_none()
+    }
+
+    /// Weapon slot the specs are made from, if any.
+    fn spec_slot(&self) -> Option<usize> {
+        self.specs.as_ref().map(|_| self.attacker(0).slot_at(self.first_attack_tick))
+    }
+
+    fn hit(&self) -> HitDistribution {
+        HitDistribution::uniform(self.style.max_hit as usize, self.style.accuracy)
+    }
+
+    fn attacker(&self, idx: usize) -> Attacker {
+        let attacker = Attacker::new(&format!("player {}", idx), self.hit(), self.style.attack_speed)
+            .with_hit_delay(self.hit_delay)
+            .with_first_attack_tick(self.first_attack_tick);
+        let attacker = self.switches.iter().fold(attacker, |attacker, switch| attacker.with_switch(switch.clone()));
+        let attacker = match &self.specs {
+            Some(plan) => attacker.with_specs(self.first_attack_tick, plan.count, plan.evaluation.hit.clone(), plan.evaluation.attack_speed, plan.hit_delay),
+            None => attacker,
+        };
+        self.downtime.iter().fold(attacker, |attacker, window| attacker.with_downtime(window.start, window.end))
+    }
+}
+
+/// Kill-time CDF (capped for plotting) and exact moments for one monster.
+/// Attack counts are the lead player's.
+struct MonsterKill {
+    kill_times: Vec<f64>,
+    /// The same CDF run out to `EXPECTATION_MASS` (or the cap when higher), for combining kills.
+    full_cdf: Cdf,
+    expected_hits: f64,
+    hits_variance: f64,
+    expected_ticks: f64,
+    ticks_variance: f64,
+    damage_shares: Vec<DamageShare>,
+    convergence: Convergence,
+    overkill: Overkill,
+    /// Remaining HP at each tick of `kill_times`.
+    hp_bands: Vec<HpBand>,
+}
+
+/// The player's weapon alone against a monster that never heals, solved
+/// exactly on the HP chain.
+fn weapon_kill(hp: usize, max_hit: usize, accuracy: f64, cap: f64, max_ticks: usize, attack_speed: usize, hit_delay: usize) -> MonsterKill {
+    let WeaponKillStats { mut kill_times, expected_hits, expected_ticks, overkill, mut hp_bands, .. } = weapon_kill_times_markov_per_tick_with_delay(
+        hp, max_hit, accuracy, cap.max(EXPECTATION_MASS), max_ticks, attack_speed, 0, hit_delay
+    );
+    let full_cdf = Cdf::new(kill_times.clone());
+    let cap_len = full_cdf.quantile(cap).map_or(kill_times.len(), |t| t + 1);
+    kill_times.truncate(cap_len);
+    hp_bands.truncate(cap_len);
+    let kernel = HitKernel::new(max_hit, accuracy);
+    let hits_to_kill = kernel.hits_to_kill(hp);
+    let convergence = Cdf::new(kill_times.clone()).convergence(cap, hp == 0 || kernel.can_damage());
+    MonsterKill {
+        kill_times,
+        full_cdf,
+        expected_hits,
+        hits_variance: hits_to_kill.variance,
+        expected_ticks,
+        ticks_variance: kill_ticks_variance(&hits_to_kill, attack_speed),
+        damage_shares: vec![DamageShare { player: 0, expected_attacks: expected_hits, expected_damage: hp as f64, share: 1.0 }],
+        convergence,
+        overkill,
+        hp_bands,
+    }
+}
+
+/// Mean and variance of how many of `attacker`'s attacks land while the
+/// monster is alive; the k-th is made iff the monster survives until it lands.
+fn attacks_made(attacker: &Attacker, cdf: &Cdf, max_ticks: usize) -> (f64, f64) {
+    let (mut mean, mut second_moment) = (0.0, 0.0);
+    for (k, (landing, _)) in attacker.hits(max_ticks).into_iter().enumerate() {
+        if landing >= cdf.probs.len() {
+            break;
+        }
+        let p_made = if landing == 0 { 1.0 } else { 1.0 - cdf.probs[landing - 1] };
+        mean += p_made;
+        second_moment += (2 * k + 1) as f64 * p_made;
+    }
+    (mean, second_moment - mean * mean)
+}
+
+/// The party's weapons (the first `players` attackers) plus helpers such as
+/// a thrall, credited to the lead player, and any monster heals; moments come
+/// from the tick CDF run out to `EXPECTATION_MASS`, which stays correct when
+/// heals move the chain back up, and are lower bounds if the run is truncated.
+fn scheduled_kill(schedule: &AttackSchedule, players: usize, hp: usize, cap: f64, max_ticks: usize) -> MonsterKill {
+    let run = schedule.run(hp, cap.max(EXPECTATION_MASS), max_ticks);
+    let full = &run.cdf;
+    let killable = hp == 0 || schedule.can_damage();
+    let helper_damage: f64 = run.damage_dealt[players..].iter().sum();
+    let total_damage: f64 = run.damage_dealt.iter().sum();
+    let damage_shares: Vec<DamageShare> = (0..players)
+        .map(|player| {
+            let expected_damage = run.damage_dealt[player] + if player == 0 { helper_damage } else { 0.0 };
+            DamageShare {
+                player,
+                expected_attacks: attacks_made(&schedule.attackers[player], full, max_ticks).0,
+                expected_damage,
+                share: if total_damage > 0.0 { expected_damage / total_damage } else { 0.0 },
+            }
+        })
+        .collect();
+    let (expected_hits, hits_variance) = attacks_made(&schedule.attackers[0], full, max_ticks);
+    let cap_len = full.quantile(cap).map_or(full.probs.len(), |t| t + 1);
+    let kill_times = full.probs[..cap_len].to_vec();
+    let hp_bands = run.hp_bands[..cap_len].to_vec();
+    let convergence = Cdf::new(kill_times.clone()).convergence(cap, killable);
+    let (expected_ticks, ticks_variance) = if killable { (full.mean(), full.variance()) } else { (f64::INFINITY, f64::INFINITY) };
+    MonsterKill {
+        kill_times,
+        full_cdf: full.clone(),
+        expected_hits: if killable { expected_hits } else { f64::INFINITY },
+        hits_variance: if killable { hits_variance } else { f64::INFINITY },
+        expected_ticks,
+        ticks_variance,
+        damage_shares,
+        convergence,
+        overkill: run.overkill,
+        hp_bands,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+    use serde_json::json;
+
+    const RULES: RoomRules = RoomRules { combat_types: &["melee"], tick_model: TickModel::new(0) };
+
+    fn damage_over_time(result: &serde_json::Value, monster: usize) -> Vec<String> {
+        serde_json::from_value(result["results"][monster]["damage_over_time"].clone()).unwrap()
+    }
+
+    #[test]
+    fn rooms_with_different_rules_align_the_encounter_differently() {
+        let party = [test_fixtures::player("Dragon scimitar", 4, &[])];
+        let room = test_fixtures::room(vec![
+            test_fixtures::monster("First", 120, json!({})),
+            test_fixtures::monster("Second", 120, json!({})),
+        ]);
+        let config = test_fixtures::config(json!({}));
+        let anvil = RoomRules { combat_types: &["melee"], tick_model: TickModel::new(0).with_alignment_cycle(4).with_downtime(20, 15) };
+        let spread_out = RoomRules { combat_types: &["melee"], tick_model: TickModel::new(0).with_alignment_cycle(3).with_retarget_delay(3) };
+        let encounter = |rules: &RoomRules| -> Vec<(usize, f64)> {
+            let result = simulate_room(&party, &room, &config, rules);
+            result["encounter_kill_times"].as_array().unwrap().iter()
+                .map(|point| (point["tick"].as_u64().unwrap() as usize, point["probability"].as_f64().unwrap()))
+                .collect()
+        };
+        let (anvil, spread_out) = (encounter(&anvil), encounter(&spread_out));
+        // Completion only ever moves at each room's own cycle boundaries
+        let steps = |cdf: &[(usize, f64)]| -> Vec<usize> { cdf.windows(2).filter(|w| w[1].1 > w[0].1).map(|w| w[1].0).collect() };
+        assert!(steps(&anvil).iter().all(|tick| tick % 4 == 0));
+        assert!(steps(&spread_out).iter().all(|tick| tick % 3 == 0));
+        assert_ne!(anvil, spread_out);
+        let mean = |cdf: &[(usize, f64)]| -> f64 { cdf.iter().map(|&(_, c)| 1.0 - c).sum() };
+        assert!(mean(&anvil) > mean(&spread_out), "anvil trips should cost more than walking");
+    }
+
+    fn barrager() -> [Player; 1] {
+        let mut player = test_fixtures::player("Dragon scimitar", 4, &[]);
+        player.gear_sets.mage.selected_weapon = Some(test_fixtures::weapon("Kodai wand", "Staff", 4, "Magic"));
+        [player]
+    }
+
+    fn barrage_room(monsters: usize, hp: u32, magic_defence: i32) -> Room {
+        test_fixtures::room((0..monsters).map(|idx| {
+            let mut monster = test_fixtures::monster(&format!("Stacked {idx}"), hp, json!({}));
+            monster.defensive.magic = magic_defence;
+            monster
+        }).collect())
+    }
+
+    #[test]
+    fn aoe_waves_stop_at_the_target_cap() {
+        let config = test_fixtures::config(json!({ "aoe": { "spell": "Ice barrage" } }));
+        let result = simulate_room(&barrager(), &barrage_room(11, 10, 0), &config, &RULES);
+        assert_eq!(result["aoe"]["targets"], json!((0..9).collect::<Vec<_>>()));
+        assert_eq!(result["aoe"]["chosen"], true);
+        assert_eq!(result["results"].as_array().unwrap().len(), 11);
+
+        let shadow = {
+            let [mut player] = barrager();
+            player.gear_sets.mage.selected_weapon = Some(test_fixtures::weapon("Tumeken's shadow", "Powered Staff", 5, "Magic"));
+            [player]
+        };
+        let rejected = simulate_room(&shadow, &barrage_room(3, 10, 0), &config, &RULES);
+        assert!(rejected["error"].as_str().unwrap().contains("cannot cast Ice barrage"));
+    }
+
+    #[test]
+    fn aoe_is_chosen_only_when_it_is_faster() {
+        let config = test_fixtures::config(json!({ "aoe": { "spell": "Ice barrage" } }));
+        let stacked = simulate_room(&barrager(), &barrage_room(4, 30, 0), &config, &RULES);
+        // Spells barely land on these, while the scimitar still hits
+        let warded = simulate_room(&barrager(), &barrage_room(2, 60, 2000), &config, &RULES);
+        for (result, chosen) in [(&stacked, true), (&warded, false)] {
+            let aoe = &result["aoe"];
+            assert_eq!(aoe["chosen"], chosen);
+            assert_eq!(aoe["aoe_ticks"].as_f64().unwrap() < aoe["single_target_ticks"].as_f64().unwrap(), chosen);
+        }
+    }
+
+    #[test]
+    fn aoe_casts_are_counted_once_for_all_their_targets() {
+        let config = test_fixtures::config(json!({ "aoe": { "spell": "Ice barrage" } }));
+        let result = simulate_room(&barrager(), &barrage_room(4, 30, 0), &config, &RULES);
+        let casts = result["aoe"]["expected_casts"].as_f64().unwrap();
+        let per_target: f64 = result["results"].as_array().unwrap().iter().map(|monster| monster["expected_hits"].as_f64().unwrap()).sum();
+        assert!((result["total_hits"].as_f64().unwrap() - casts).abs() < 1e-9);
+        // Casts last until the toughest-rolling target dies
+        let longest = result["results"].as_array().unwrap().iter().map(|monster| monster["expected_hits"].as_f64().unwrap()).fold(0.0, f64::max);
+        assert!(casts >= longest && casts < per_target);
+    }
+
+    #[test]
+    fn burning_claws_burn_only_monsters_without_burn_immunity() {
+        let party = [test_fixtures::player("Burning claws", 4, &[])];
+        let room = test_fixtures::room(vec![
+            test_fixtures::monster("Burnable", 200, json!({})),
+            test_fixtures::monster("Fireproof", 200, json!({ "burn": true })),
+        ]);
+        let result = simulate_room(&party, &room, &test_fixtures::config(json!({ "spec": { "energy": 30 } })), &RULES);
+        assert_eq!(damage_over_time(&result, 0), ["burn"]);
+        assert!(damage_over_time(&result, 1).is_empty());
+        // Without the spec the claws never burn
+        let unspecced = simulate_room(&party, &room, &test_fixtures::config(json!({})), &RULES);
+        assert!(damage_over_time(&unspecced, 0).is_empty());
+    }
+
+    #[test]
+    fn kill_order_search_times_vengeance_from_each_fight_start() {
+        let party = [test_fixtures::player("Dragon scimitar", 4, &[])];
+        let room = test_fixtures::room(vec![
+            test_fixtures::monster("Weak", 15, json!({})),
+            test_fixtures::monster("Tanky", 150, json!({})),
+            test_fixtures::monster("Middling", 80, json!({})),
+        ]);
+        let config = test_fixtures::config(json!({ "kill_order": {}, "vengeance": { "cast_tick": 7 } }));
+        let result = simulate_room(&party, &room, &config, &RULES);
+        // Only the first fight sees the cast, and it does most for the longest
+        // fight. Reusing each monster's fight from the listed order for every
+        // order gives them all the same time and keeps the listed one
+        assert_eq!(result["kill_order"]["order"], json!([1, 2, 0]));
+        let room_ticks = result["kill_order"]["room_ticks"].as_f64().unwrap();
+        let listed_ticks = result["kill_order"]["listed_order_ticks"].as_f64().unwrap();
+        let total_ticks = result["total_expected_ticks"].as_f64().unwrap();
+        assert!(room_ticks < listed_ticks);
+        assert!((room_ticks - total_ticks).abs() < 1e-3, "search {room_ticks} vs simulated {total_ticks}");
+    }
+
+    #[test]
+    fn warhammer_specs_drain_defence_for_every_later_hit() {
+        let party = [test_fixtures::player("Dragon warhammer", 6, &[])];
+        let monster = test_fixtures::monster("Tank", 300, json!({}));
+        let config = test_fixtures::config(json!({ "spec": {} }));
+        let combat_types = ["melee".to_string()];
+        let fighters = MonsterFight::fighters(&party, &monster, &config, &combat_types, 1, 0);
+        assert_eq!(fighters[0].specs.as_ref().map(|plan| plan.count), Some(2));
+        let drain = defence_drain(&party, &fighters[0], &monster, &config, &combat_types, 1, 0).unwrap();
+        assert_eq!(drain.slot, 1);
+
+        // Defence 50 drops to 35, then 25
+        for (drained, defence) in drain.drained.iter().zip([35, 25]) {
+            let mut weakened = monster.clone();
+            weakened.skills.def = defence;
+            let attacker = MonsterFight::fighters(&party, &weakened, &config, &combat_types, 1, 0)[0].attacker(0);
+            let expected: Vec<HitDistribution> = (0..=attacker.switches.len()).map(|slot| attacker.slot_hit(slot).clone()).collect();
+            assert_eq!(drained[0], expected);
+        }
+        let undrained = fighters[0].attacker(0);
+        assert!(drain.drained[0][0][2].pmf.probs[0] < undrained.slot_hit(2).pmf.probs[0]);
+    }
+
+    #[test]
+    fn specs_are_reported_and_speed_up_the_room() {
+        let party = [test_fixtures::player("Saradomin godsword", 6, &[])];
+        let room = test_fixtures::room(vec![test_fixtures::monster("Tank", 300, json!({}))]);
+        let plain = simulate_room(&party, &room, &test_fixtures::config(json!({})), &RULES);
+        let specced = simulate_room(&party, &room, &test_fixtures::config(json!({ "spec": { "energy": 100 } })), &RULES);
+        assert!(plain["results"][0]["special_attack"].is_null());
+        let spec = &specced["results"][0]["special_attack"];
+        assert_eq!(spec["name"], "Healing Blade");
+        assert_eq!(spec["specs"], 2);
+        let expected_damage = spec["expected_damage"].as_f64().unwrap();
+        assert!((spec["expected_heal"].as_f64().unwrap() - 0.5 * expected_damage).abs() < 1e-9);
+        assert!(specced["total_expected_ticks"].as_f64().unwrap() < plain["total_expected_ticks"].as_f64().unwrap());
+    }
+
+    #[test]
+    fn burning_claws_spec_burns_from_its_own_slot() {
+        let party = [test_fixtures::player("Burning claws", 4, &[])];
+        let room = test_fixtures::room(vec![test_fixtures::monster("Burnable", 200, json!({}))]);
+        let config = test_fixtures::config(json!({ "spec": { "energy": 30 } }));
+        let fight = MonsterFight::new(&party, &room, &room.monsters[0], &config, &["melee".to_string()], None, 0);
+        let burn = &fight.schedule.dots[0];
+        assert_eq!(burn.kind, "burn");
+        let spec_slot = fight.fighters[0].spec_slot().unwrap();
+        assert!(burn.chance_from(0, spec_slot) > 0.0);
+        assert_eq!(burn.chance_from(0, 0), 0.0);
+    }
+
+    #[test]
+    fn damage_taken_does_not_depend_on_the_plotting_cap() {
+        let party = [test_fixtures::player("Dragon scimitar", 4, &[])];
+        let room = test_fixtures::room(vec![test_fixtures::monster("Hitter", 120, json!({}))]);
+        let damage = |cap: f64| {
+            let result = simulate_room(&party, &room, &test_fixtures::config(json!({ "cap": cap })), &RULES);
+            result["results"][0]["damage_taken"]["expected_damage"].as_f64().unwrap()
+        };
+        assert!(damage(0.5) > 0.0);
+        assert!((damage(0.5) - damage(0.999)).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,608 @@
+use crate::damage_over_time::DamageOverTime;
+use crate::distribution::{Cdf, Pmf};
+use crate::healing::Heal;
+use crate::reflect::{recoil_damage, vengeance_damage, Reflection};
+use osrs_shared_types::{HpBand, HpSnapshot, Overkill};
+
+use crate::markov::{hp_band, HitKernel};
+
+/// Damage dealt by a single attack: `pmf.probs[k]` = P(k damage).
+#[derive(Clone, Debug, PartialEq)]
+pub struct HitDistribution {
+    pub pmf: Pmf,
+}
+
+impl HitDistribution {
+    pub fn from_pmf(probs: Vec<f64>) -> Self {
+        HitDistribution { pmf: Pmf::new(probs) }
+    }
+
+    /// Uniform damage on [0, max_hit] on a hit, as rolled by player attacks.
+    pub fn uniform(max_hit: usize, accuracy: f64) -> Self {
+        let p_damage = accuracy / (max_hit as f64 + 1.0);
+        let mut probs = vec![p_damage; max_hit + 1];
+        probs[0] += 1.0 - accuracy;
+        HitDistribution::from_pmf(probs)
+    }
+
+    pub fn max_hit(&self) -> usize {
+        self.pmf.len().saturating_sub(1)
+    }
+
+    pub fn kernel(&self) -> HitKernel {
+        HitKernel::from_pmf(&self.pmf.probs)
+    }
+}
+
+/// From `tick` on, an attacker uses a different weapon.
+#[derive(Clone, Debug)]
+pub struct WeaponSwitch {
+    pub tick: usize,
+    pub hit: HitDistribution,
+    pub attack_speed: usize,
+    pub hit_delay: usize,
+}
+
+/// Ticks in [start, end) during which an attacker cannot attack.
+#[derive(Clone, Copy, Debug)]
+pub struct Downtime {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Anything that damages the monster on a fixed cadence: the player's weapon,
+/// a thrall, a teammate.
+#[derive(Clone, Debug)]
+pub struct Attacker {
+    pub name: String,
+    pub hit: HitDistribution,
+    pub attack_speed: usize,
+    pub first_attack_tick: usize,
+    /// Ticks between an attack and its hitsplat landing.
+    pub hit_delay: usize,
+    /// No attacks are made after this tick.
+    pub last_attack_tick: Option<usize>,
+    pub switches: Vec<WeaponSwitch>,
+    pub downtime: Vec<Downtime>,
+}
+
+impl Attacker {
+    pub fn new(name: &str, hit: HitDistribution, attack_speed: usize) -> Self {
+        Attacker {
+            name: name.to_string(),
+            hit,
+            attack_speed,
+            first_attack_tick: 0,
+            hit_delay: 0,
+            last_attack_tick: None,
+            switches: Vec::new(),
+            downtime: Vec::new(),
+        }
+    }
+
+    pub fn with_first_attack_tick(mut self, tick: usize) -> Self {
+        self.first_attack_tick = tick;
+        self
+    }
+
+    pub fn with_hit_delay(mut self, hit_delay: usize) -> Self {
+        self.hit_delay = hit_delay;
+        self
+    }
+
+    pub fn until(mut self, tick: usize) -> Self {
+        self.last_attack_tick = Some(tick);
+        self
+    }
+
+    pub fn with_switch(mut self, switch: WeaponSwitch) -> Self {
+        self.switches.push(switch);
+        self.switches.sort_by_key(|s| s.tick);
+        self
+    }
+
+    /// One special attack made on `tick`, which should be one of the
+    /// attacker's attack ticks, with the weapon in use before it picked back
+    /// up for the next attack.
+    pub fn with_spec(self, tick: usize, hit: HitDistribution, attack_speed: usize, hit_delay: usize) -> Self {
+        self.with_specs(tick, 1, hit, attack_speed, hit_delay)
+    }
+
+    /// `count` special attacks back to back from `tick`, all made from one
+    /// weapon slot, as in `with_spec`.
+    pub fn with_specs(self, tick: usize, count: usize, hit: HitDistribution, attack_speed: usize, hit_delay: usize) -> Self {
+        if count == 0 {
+            return self;
+        }
+        let slot = self.slot_at(tick);
+        let (resume_speed, resume_delay) = self.slot_timing(slot);
+        let last_spec = tick + (count - 1) * attack_speed.max(1);
+        let resume = WeaponSwitch { tick: last_spec + 1, hit: self.slot_hit(slot).clone(), attack_speed: resume_speed, hit_delay: resume_delay };
+        self.with_switch(WeaponSwitch { tick, hit, attack_speed, hit_delay }).with_switch(resume)
+    }
+
+    pub fn with_downtime(mut self, start: usize, end: usize) -> Self {
+        self.downtime.push(Downtime { start, end });
+        self
+    }
+
+    /// Weapon slot in use at `tick`: 0 is the starting weapon, i + 1 is `switches[i]`.
+    pub fn slot_at(&self, tick: usize) -> usize {
+        self.switches.iter().rposition(|s| s.tick <= tick).map_or(0, |i| i + 1)
+    }
+
+    fn slot_timing(&self, slot: usize) -> (usize, usize) {
+        match slot {
+            0 => (self.attack_speed, self.hit_delay),
+            _ => (self.switches[slot - 1].attack_speed, self.switches[slot - 1].hit_delay),
+        }
+    }
+
+    pub fn slot_hit(&self, slot: usize) -> &HitDistribution {
+        match slot {
+            0 => &self.hit,
+            _ => &self.switches[slot - 1].hit,
+        }
+    }
+
+    /// (landing tick, weapon slot) of every attack made up to `max_tick`.
+    ///
+    /// An attack that falls in a downtime window waits for the window to end;
+    /// after a switch the attack timer of the previous weapon still applies.
+    pub fn hits(&self, max_tick: usize) -> Vec<(usize, usize)> {
+        let mut hits = Vec::new();
+        let mut tick = self.first_attack_tick;
+        while tick <= max_tick && self.last_attack_tick.is_none_or(|last| tick <= last) {
+            if let Some(window) = self.downtime.iter().find(|d| d.start <= tick && tick < d.end) {
+                tick = window.end;
+                continue;
+            }
+            let slot = self.slot_at(tick);
+            let (attack_speed, hit_delay) = self.slot_timing(slot);
+            hits.push((tick + hit_delay, slot));
+            tick += attack_speed.max(1);
+        }
+        hits
+    }
+}
+
+/// The monster's defence lowered by damaging hits from one weapon slot of one
+/// attacker (a defence-draining spec). Each such hit drains once more, up to
+/// `drained.len()` times, and every attack after it rolls against the lower
+/// defence.
+#[derive(Clone, Debug)]
+pub struct DefenceDrain {
+    pub attacker: usize,
+    pub slot: usize,
+    /// `drained[d][idx][slot]` is attacker `idx`'s hit from weapon `slot`
+    /// after d + 1 drains; attackers past the end of `drained[d]` keep their own.
+    pub drained: Vec<Vec<Vec<HitDistribution>>>,
+}
+
+impl DefenceDrain {
+    pub fn applies_to(&self, attacker: usize, slot: usize) -> bool {
+        self.attacker == attacker && self.slot == slot
+    }
+}
+
+/// Several attackers hitting one monster on their own cadences, any
+/// damage-over-time effects their hits apply, damage reflected from the
+/// monster's own attacks, any heals the monster gets in between, and any
+/// defence drained along the way.
+#[derive(Clone, Debug, Default)]
+pub struct AttackSchedule {
+    pub attackers: Vec<Attacker>,
+    pub heals: Vec<Heal>,
+    pub dots: Vec<DamageOverTime>,
+    pub reflection: Option<Reflection>,
+    pub drain: Option<DefenceDrain>,
+}
+
+impl AttackSchedule {
+    pub fn new() -> Self {
+        AttackSchedule::default()
+    }
+
+    pub fn with_attacker(mut self, attacker: Attacker) -> Self {
+        self.attackers.push(attacker);
+        self
+    }
+
+    /// Whether any attacker, with any of its weapons, or reflected damage can do damage.
+    pub fn can_damage(&self) -> bool {
+        self.attackers.iter().any(|a| (0..=a.switches.len()).any(|slot| a.slot_hit(slot).kernel().can_damage()))
+            || self.reflection.as_ref().is_some_and(Reflection::can_damage)
+    }
+
+    pub fn with_heal(mut self, heal: Heal) -> Self {
+        self.heals.push(heal);
+        self
+    }
+
+    pub fn with_dot(mut self, dot: DamageOverTime) -> Self {
+        self.dots.push(dot);
+        self
+    }
+
+    pub fn with_reflection(mut self, reflection: Reflection) -> Self {
+        self.reflection = Some(reflection);
+        self
+    }
+
+    pub fn with_drain(mut self, drain: DefenceDrain) -> Self {
+        self.drain = Some(drain);
+        self
+    }
+
+    /// Most drains the schedule's hits can apply.
+    pub fn max_drains(&self) -> usize {
+        self.drain.as_ref().map_or(0, |drain| drain.drained.len())
+    }
+
+    /// Attacker `idx`'s hit from weapon `slot` once defence has been drained `drains` times.
+    pub fn hit_after_drains(&self, drains: usize, idx: usize, slot: usize) -> &HitDistribution {
+        let drained = drains.checked_sub(1).and_then(|d| self.drain.as_ref()?.drained.get(d)?.get(idx)?.get(slot));
+        drained.unwrap_or_else(|| self.attackers[idx].slot_hit(slot))
+    }
+
+    /// Per-tick death CDF of a monster at `hp`: entry t is P(dead once every
+    /// hitsplat landing on or before tick t has been applied). On each tick,
+    /// vengeance is cast first, then damage-over-time hitsplats land, then
+    /// attacks, then damage reflected from the monster's hit, then heals.
+    /// Stops once `cap` is reached or after `max_tick`.
+    ///
+    /// The state is propagated forward, so HP may move up as well as down;
+    /// only death is absorbing.
+    pub fn kill_cdf(&self, hp: usize, cap: f64, max_tick: usize) -> Cdf {
+        self.run(hp, cap, max_tick).cdf
+    }
+
+    /// The death CDF as in `kill_cdf`, plus the damage each attacker dealt.
+    /// A schedule that can never do damage stops at tick 0.
+    pub fn run(&self, hp: usize, cap: f64, max_tick: usize) -> ScheduleRun {
+        self.propagate(hp, cap, max_tick).0
+    }
+
+    /// Full remaining-HP distribution at `tick`, after that tick's hitsplats and heals.
+    pub fn hp_distribution_at(&self, hp: usize, tick: usize) -> HpSnapshot {
+        // A cap above 1 is never reached, so the run always gets to `tick`
+        let (_, distribution) = self.propagate(hp, f64::INFINITY, tick);
+        HpSnapshot { tick, distribution }
+    }
+
+    /// `run`, also returning the HP state vector at the tick it stopped on.
+    fn propagate(&self, hp: usize, cap: f64, max_tick: usize) -> (ScheduleRun, Vec<f64>) {
+        if hp > 0 && !self.can_damage() {
+            let mut state = vec![0.0; hp + 1];
+            state[hp] = 1.0;
+            let run = ScheduleRun {
+                cdf: Cdf::new(vec![0.0]),
+                damage_dealt: vec![0.0; self.attackers.len()],
+                overkill: Overkill::default(),
+                hp_bands: vec![hp_band(&state, 0)],
+            };
+            return (run, state);
+        }
+        // kernels[drains][attacker][slot]
+        let kernels: Vec<Vec<Vec<HitKernel>>> = (0..=self.max_drains())
+            .map(|drains| {
+                self.attackers
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, a)| (0..=a.switches.len()).map(|slot| self.hit_after_drains(drains, idx, slot).kernel()).collect())
+                    .collect()
+            })
+            .collect();
+        let mut hits: Vec<(usize, usize, usize)> = self
+            .attackers
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, a)| a.hits(max_tick).into_iter().map(move |(tick, slot)| (tick, idx, slot)))
+            .collect();
+        hits.sort();
+        let mut heals: Vec<(usize, usize)> = self
+            .heals
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, heal)| heal.ticks(max_tick).into_iter().map(move |tick| (tick, idx)))
+            .collect();
+        heals.sort();
+
+        let mut monster_hits = self
+            .reflection
+            .as_ref()
+            .map(|reflection| reflection.hit_ticks(max_tick).collect::<Vec<_>>())
+            .unwrap_or_default()
+            .into_iter()
+            .peekable();
+
+        // One HP state vector per combination of effect states
+        let effects = EffectLayers::new(&self.dots, self.reflection.as_ref(), self.drain.as_ref());
+        let mut layers = vec![vec![0.0; hp + 1]; effects.count];
+        layers[0][hp] = 1.0;
+        let mut probs = Vec::new();
+        let mut damage_dealt = vec![0.0; self.attackers.len()];
+        let mut overkill = Overkill::default();
+        let mut hp_bands = Vec::new();
+        let mut next = hits.iter().peekable();
+        let mut next_heal = heals.iter().peekable();
+        for tick in 0..=max_tick {
+            if self.reflection.as_ref().is_some_and(|reflection| reflection.casts_on(tick)) {
+                layers = effects.cast_vengeance(&layers);
+            }
+            for (effect, dot) in self.dots.iter().enumerate() {
+                if dot.ticks_on(tick) {
+                    let (stepped, dealt) = effects.tick(effect, &layers);
+                    layers = stepped;
+                    // Credited to the first attacker able to apply it
+                    if let Some(applier) = dot.appliers.first() {
+                        damage_dealt[applier.attacker] += dealt;
+                    }
+                }
+            }
+            while let Some(&&(landing, idx, slot)) = next.peek() {
+                if landing > tick { break; }
+                for (layer_idx, layer) in layers.iter().enumerate() {
+                    let kernel = &kernels[effects.drains(layer_idx)][idx][slot];
+                    damage_dealt[idx] += kernel.expected_damage(layer);
+                    kernel.accumulate_overkill(layer, &mut overkill);
+                }
+                layers = effects.hit(idx, slot, &kernels, &layers);
+                next.next();
+            }
+            while let Some(&(hit_tick, hit)) = monster_hits.peek() {
+                if hit_tick > tick { break; }
+                if let Some(reflection) = &self.reflection {
+                    let (stepped, dealt) = effects.reflect(reflection, hit, &layers);
+                    layers = stepped;
+                    if let Some(owner) = damage_dealt.get_mut(reflection.owner) {
+                        *owner += dealt;
+                    }
+                }
+                monster_hits.next();
+            }
+            while let Some(&&(heal_tick, idx)) = next_heal.peek() {
+                if heal_tick > tick { break; }
+                for layer in &mut layers {
+                    *layer = self.heals[idx].apply(layer);
+                }
+                next_heal.next();
+            }
+            let state = EffectLayers::hp_state(&layers);
+            probs.push(state[0]);
+            hp_bands.push(hp_band(&state, tick));
+            if state[0] >= cap {
+                break;
+            }
+        }
+        (ScheduleRun { cdf: Cdf::new(probs), damage_dealt, overkill, hp_bands }, EffectLayers::hp_state(&layers))
+    }
+}
+
+/// Layout of the joint effect state: each damage-over-time effect is
+/// inactive (0) or at stage s (s + 1), vengeance, when used, is armed or
+/// not, and defence has been drained some number of times; the combinations
+/// are numbered in mixed radix with vengeance above the effects and drains
+/// highest. Without effects there is a single layer and the chain is the
+/// plain HP chain.
+struct EffectLayers<'a> {
+    dots: &'a [DamageOverTime],
+    drain: Option<&'a DefenceDrain>,
+    strides: Vec<usize>,
+    /// Layers with vengeance unarmed; armed layers follow them.
+    dot_count: usize,
+    /// Layers for one number of drains.
+    drain_stride: usize,
+    count: usize,
+}
+
+impl<'a> EffectLayers<'a> {
+    fn new(dots: &'a [DamageOverTime], reflection: Option<&Reflection>, drain: Option<&'a DefenceDrain>) -> Self {
+        let mut strides = Vec::with_capacity(dots.len());
+        let mut dot_count = 1;
+        for dot in dots {
+            strides.push(dot_count);
+            dot_count *= dot.damage.len() + 1;
+        }
+        let vengeance = reflection.is_some_and(|reflection| reflection.vengeance_cast.is_some());
+        let drain_stride = if vengeance { 2 * dot_count } else { dot_count };
+        let drains = drain.map_or(0, |drain| drain.drained.len());
+        EffectLayers { dots, drain, strides, dot_count, drain_stride, count: drain_stride * (drains + 1) }
+    }
+
+    /// Times defence has been drained in `layer`.
+    fn drains(&self, layer: usize) -> usize {
+        layer / self.drain_stride
+    }
+
+    /// Whether vengeance is armed in `layer`.
+    fn armed(&self, layer: usize) -> bool {
+        self.drain_stride > self.dot_count && layer % self.drain_stride >= self.dot_count
+    }
+
+    fn status(&self, layer: usize, effect: usize) -> usize {
+        layer / self.strides[effect] % (self.dots[effect].damage.len() + 1)
+    }
+
+    fn with_status(&self, layer: usize, effect: usize, status: usize) -> usize {
+        layer - self.status(layer, effect) * self.strides[effect] + status * self.strides[effect]
+    }
+
+    /// HP distribution over every layer.
+    fn hp_state(layers: &[Vec<f64>]) -> Vec<f64> {
+        let mut state = vec![0.0; layers[0].len()];
+        for layer in layers {
+            for (total, &p) in state.iter_mut().zip(layer) {
+                *total += p;
+            }
+        }
+        state
+    }
+
+    /// Layers after an attack by `attacker`'s weapon `slot`, rolled with
+    /// `kernels[drains][attacker][slot]`; a damaging hit may start any
+    /// inactive effect it can apply and drain defence once more.
+    fn hit(&self, attacker: usize, slot: usize, kernels: &[Vec<Vec<HitKernel>>], layers: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        let mut out = vec![vec![0.0; layers[0].len()]; self.count];
+        for (layer_idx, layer) in layers.iter().enumerate() {
+            let kernel = &kernels[self.drains(layer_idx)][attacker][slot];
+            let stepped = kernel.step(layer);
+            let drains = self.drain.is_some_and(|drain| drain.applies_to(attacker, slot)) && layer_idx + self.drain_stride < self.count;
+            // Where the damaging part goes, split by which effects start
+            let mut pieces = vec![(if drains { layer_idx + self.drain_stride } else { layer_idx }, 1.0)];
+            for (effect, dot) in self.dots.iter().enumerate() {
+                let chance = dot.chance_from(attacker, slot);
+                if chance == 0.0 || self.status(layer_idx, effect) != 0 {
+                    continue;
+                }
+                pieces = pieces
+                    .into_iter()
+                    .flat_map(|(target, weight)| [(target, weight * (1.0 - chance)), (self.with_status(target, effect, 1), weight * chance)])
+                    .collect();
+            }
+            if pieces == [(layer_idx, 1.0)] {
+                for (total, p) in out[layer_idx].iter_mut().zip(stepped) {
+                    *total += p;
+                }
+                continue;
+            }
+            for (hp, (&before, &after)) in layer.iter().zip(&stepped).enumerate() {
+                let missed = if hp == 0 { before } else { before * kernel.damage[0] };
+                out[layer_idx][hp] += missed;
+                for &(target, weight) in &pieces {
+                    out[target][hp] += weight * (after - missed);
+                }
+            }
+        }
+        out
+    }
+
+    /// Layers after vengeance is cast; a cast while armed changes nothing.
+    fn cast_vengeance(&self, layers: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        let mut out = layers.to_vec();
+        if self.drain_stride == self.dot_count {
+            return out;
+        }
+        for layer_idx in (0..self.count).filter(|&layer| !self.armed(layer)) {
+            let unarmed = std::mem::replace(&mut out[layer_idx], vec![0.0; layers[layer_idx].len()]);
+            for (total, p) in out[layer_idx + self.dot_count].iter_mut().zip(&unarmed) {
+                *total += p;
+            }
+        }
+        out
+    }
+
+    /// Layers after the `hit`-th monster attack is reflected back, with the
+    /// expected HP it removed; a damaging hit uses up an armed vengeance.
+    fn reflect(&self, reflection: &Reflection, hit: usize, layers: &[Vec<f64>]) -> (Vec<Vec<f64>>, f64) {
+        let recoils = reflection.recoils(hit);
+        let mut out = vec![vec![0.0; layers[0].len()]; self.count];
+        let mut dealt = 0.0;
+        for (layer_idx, layer) in layers.iter().enumerate() {
+            let armed = self.armed(layer_idx);
+            out[layer_idx][0] += layer[0];
+            for (damage, &p_damage) in reflection.monster_hit.probs.iter().enumerate() {
+                if p_damage == 0.0 {
+                    continue;
+                }
+                let mut reflected = if recoils { recoil_damage(damage) } else { 0 };
+                let mut target = layer_idx;
+                if armed && damage > 0 {
+                    reflected += vengeance_damage(damage);
+                    target -= self.dot_count;
+                }
+                for (hp, &p) in layer.iter().enumerate().skip(1) {
+                    out[target][hp.saturating_sub(reflected)] += p * p_damage;
+                    dealt += p * p_damage * reflected.min(hp) as f64;
+                }
+            }
+        }
+        (out, dealt)
+    }
+
+    /// Layers after `effect` deals its damage, with the expected HP it removed.
+    fn tick(&self, effect: usize, layers: &[Vec<f64>]) -> (Vec<Vec<f64>>, f64) {
+        let dot = &self.dots[effect];
+        let mut out = vec![vec![0.0; layers[0].len()]; self.count];
+        let mut dealt = 0.0;
+        for (layer_idx, layer) in layers.iter().enumerate() {
+            let status = self.status(layer_idx, effect);
+            if status == 0 {
+                for (total, &p) in out[layer_idx].iter_mut().zip(layer) {
+                    *total += p;
+                }
+                continue;
+            }
+            let damage = dot.damage[status - 1];
+            let next = dot.next_stage(status - 1).map_or(0, |stage| stage + 1);
+            let target = self.with_status(layer_idx, effect, next);
+            out[target][0] += layer[0];
+            for (hp, &p) in layer.iter().enumerate().skip(1) {
+                out[target][hp.saturating_sub(damage)] += p;
+                dealt += p * damage.min(hp) as f64;
+            }
+        }
+        (out, dealt)
+    }
+}
+
+/// Outcome of an attack schedule against one monster.
+#[derive(Clone, Debug)]
+pub struct ScheduleRun {
+    pub cdf: Cdf,
+    /// Expected HP removed by each attacker, in `attackers` order; overkill is not counted.
+    pub damage_dealt: Vec<f64>,
+    /// Wasted damage on the killing blow, over every attacker.
+    pub overkill: Overkill,
+    /// Remaining HP at each tick of `cdf`.
+    pub hp_bands: Vec<HpBand>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reflected_damage_is_credited_to_its_owner() {
+        // Monster always hits 20, so every hit recoils 3
+        let reflection = Reflection::new(Pmf::point(20), 4).with_recoil(None).with_owner(1);
+        let schedule = AttackSchedule::new()
+            .with_attacker(Attacker::new("player 0", HitDistribution::uniform(10, 0.5), 4))
+            .with_attacker(Attacker::new("player 1", HitDistribution::uniform(0, 0.0), 4))
+            .with_reflection(reflection);
+        let run = schedule.run(30, 0.9999, 400);
+        let recoiled: f64 = run.damage_dealt[1];
+        assert!(recoiled > 0.0);
+        assert!((run.damage_dealt.iter().sum::<f64>() - 30.0).abs() < 1e-3);
+        let solo = AttackSchedule::new().with_attacker(Attacker::new("player 0", HitDistribution::uniform(10, 0.5), 4)).run(30, 0.9999, 400);
+        assert!(run.damage_dealt[0] < solo.damage_dealt[0]);
+    }
+
+    #[test]
+    fn hp_distribution_and_bands_follow_the_markov_chain() {
+        let hit = HitDistribution::uniform(12, 0.7);
+        let schedule = AttackSchedule::new().with_attacker(Attacker::new("player 0", hit.clone(), 4));
+        let hp = 40;
+        // Attacks land on ticks 0, 4 and 8: three steps of the chain by tick 9
+        let kernel = hit.kernel();
+        let mut chain = vec![0.0; hp + 1];
+        chain[hp] = 1.0;
+        for _ in 0..3 {
+            chain = kernel.step(&chain);
+        }
+        let snapshot = schedule.hp_distribution_at(hp, 9);
+        assert!((snapshot.distribution.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        for (from_schedule, from_chain) in snapshot.distribution.iter().zip(&chain) {
+            assert!((from_schedule - from_chain).abs() < 1e-12);
+        }
+
+        let run = schedule.run(hp, 0.999, 400);
+        let band = &run.hp_bands[9];
+        let expected = hp_band(&chain, 9);
+        assert_eq!(band.tick, 9);
+        assert!((band.expected_hp - expected.expected_hp).abs() < 1e-9);
+        assert_eq!((band.p10, band.p50, band.p90), (expected.p10, expected.p50, expected.p90));
+        assert!(band.p10 <= band.p50 && band.p50 <= band.p90);
+        assert!((run.cdf.at(9) - chain[0]).abs() < 1e-12);
+    }
+}
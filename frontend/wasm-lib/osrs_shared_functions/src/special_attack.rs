@@ -0,0 +1,448 @@
+use osrs_shared_types::*;
+
+use crate::accuracy::{accuracy_modifiers_for, AccuracyModel, AccuracyModifier};
+use crate::schedule::HitDistribution;
+use crate::{calculate_max_hit_for_style, calculate_max_rolls_for_style, effective_attack_speed, gear_set_for};
+
+/// Chance the claws' fallback hitsplats of 1 land when all four rolls miss.
+const CLAW_FALLBACK_CHANCE: f64 = 2.0 / 3.0;
+
+/// Total damage of a dragon claws spec (Slice and Dice).
+///
+/// Up to four accuracy rolls are made and the first that hits sets the
+/// hitsplats, for a roll `x` on the given range:
+/// 1. `x` in [max/2, max - 1], then x/2, x/4 and x/4 + 1;
+/// 2. 0, `x` in [3max/8, 7max/8], then x/2 and x/2 + 1;
+/// 3. 0, 0, `x` in [max/4, 3max/4], then x + 1;
+/// 4. 0, 0, 0, `x` in [max/4, 5max/4].
+///
+/// If all four miss, the last two hitsplats are 1 each two times in three.
+/// All four hitsplats land together, so only their total matters to the HP chain.
+pub fn dragon_claws_spec(max_hit: usize, accuracy: f64) -> HitDistribution {
+    let miss = 1.0 - accuracy;
+    let mut probs = vec![0.0; 2 * max_hit + 2];
+    spread_uniform(&mut probs, accuracy, max_hit / 2, max_hit.saturating_sub(1), |x| {
+        let half = x / 2;
+        x + half + 2 * (half / 2) + 1
+    });
+    spread_uniform(&mut probs, miss * accuracy, max_hit * 3 / 8, max_hit * 7 / 8, |x| x + 2 * (x / 2) + 1);
+    spread_uniform(&mut probs, miss.powi(2) * accuracy, max_hit / 4, max_hit * 3 / 4, |x| 2 * x + 1);
+    spread_uniform(&mut probs, miss.powi(3) * accuracy, max_hit / 4, max_hit * 5 / 4, |x| x);
+    let all_miss = miss.powi(4);
+    add_mass(&mut probs, 2, all_miss * CLAW_FALLBACK_CHANCE);
+    add_mass(&mut probs, 0, all_miss * (1.0 - CLAW_FALLBACK_CHANCE));
+    HitDistribution::from_pmf(trim(probs))
+}
+
+/// Total damage of a burning claws spec (Searing Cleave).
+///
+/// Up to three accuracy rolls are made and the first that hits sets the
+/// damage, split over the remaining hitsplats: a total in [3max/4, 7max/4]
+/// on the first roll, [max/2, 3max/2] on the second and [max/4, 5max/4] on
+/// the third. A spec that misses all three deals nothing. The burn it can
+/// apply is a separate `DamageOverTime::burn` on the schedule.
+pub fn burning_claws_spec(max_hit: usize, accuracy: f64) -> HitDistribution {
+    let miss = 1.0 - accuracy;
+    let mut probs = vec![0.0; max_hit * 7 / 4 + 1];
+    spread_uniform(&mut probs, accuracy, max_hit * 3 / 4, max_hit * 7 / 4, |x| x);
+    spread_uniform(&mut probs, miss * accuracy, max_hit / 2, max_hit * 3 / 2, |x| x);
+    spread_uniform(&mut probs, miss.powi(2) * accuracy, max_hit / 4, max_hit * 5 / 4, |x| x);
+    add_mass(&mut probs, 0, miss.powi(3));
+    HitDistribution::from_pmf(trim(probs))
+}
+
+/// Total damage of a dragon dagger spec (Puncture): two hits landing together,
+/// each rolling accuracy on its own with 15% more max hit. `accuracy` is the
+/// spec's hit chance, with its boosted attack roll already applied.
+pub fn dragon_dagger_spec(max_hit: usize, accuracy: f64) -> HitDistribution {
+    catalogued("Dragon dagger").hit_distribution(max_hit, accuracy)
+}
+
+/// Damage of a Saradomin godsword spec (Healing Blade): one hit with 10% more
+/// max hit. `accuracy` is the spec's hit chance, with its doubled attack roll
+/// already applied; the healing and prayer restore do not touch the monster.
+pub fn saradomin_godsword_spec(max_hit: usize, accuracy: f64) -> HitDistribution {
+    catalogued("Saradomin godsword").hit_distribution(max_hit, accuracy)
+}
+
+/// How a special attack's damage is rolled from its boosted max hit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpecDamage {
+    /// One hit on [0, max].
+    Standard,
+    /// One hit on [min_percent% of the unboosted max, max].
+    MinHit { min_percent: usize },
+    /// Two hits landing together, each rolling accuracy on its own.
+    DoubleHit,
+    /// The dragon claws cascade, see `dragon_claws_spec`.
+    DragonClaws,
+    /// The burning claws cascade, see `burning_claws_spec`.
+    BurningClaws,
+}
+
+/// What a special attack does besides damaging the monster.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpecEffect {
+    /// Heals the player by this fraction of the damage dealt.
+    Heal(f64),
+    /// Restores the player's prayer by this fraction of the damage dealt.
+    RestorePrayer(f64),
+    /// Lowers the monster's defence by this fraction of its current level
+    /// when the spec deals damage.
+    DrainDefence(f64),
+    /// The enchanted bolt's effect always triggers on a hit.
+    GuaranteedBoltEffect,
+    /// Burns the monster with this chance when the spec deals damage.
+    Burn(f64),
+}
+
+/// A weapon's special attack, layered on the rolls of a normal attack with it.
+#[derive(Clone, Copy, Debug)]
+pub struct SpecialAttack {
+    pub weapon: &'static str,
+    pub name: &'static str,
+    /// Special attack energy used, out of 100.
+    pub energy_cost: u32,
+    /// Multiplies the max attack roll.
+    pub accuracy_multiplier: f64,
+    /// Applied on top of the weapon's own accuracy modifiers.
+    pub accuracy_modifiers: &'static [AccuracyModifier],
+    /// Multiplies the max hit.
+    pub damage_multiplier: f64,
+    pub damage: SpecDamage,
+    /// Attack type rolled against instead of the style's own; None keeps it.
+    pub hit_type: Option<&'static str>,
+    pub effects: &'static [SpecEffect],
+}
+
+pub const SPECIAL_ATTACKS: &[SpecialAttack] = &[
+    SpecialAttack {
+        weapon: "Dragon dagger",
+        name: "Puncture",
+        energy_cost: 25,
+        accuracy_multiplier: 1.15,
+        accuracy_modifiers: &[],
+        damage_multiplier: 1.15,
+        damage: SpecDamage::DoubleHit,
+        hit_type: None,
+        effects: &[],
+    },
+    SpecialAttack {
+        weapon: "Dragon claws",
+        name: "Slice and Dice",
+        energy_cost: 50,
+        accuracy_multiplier: 1.0,
+        accuracy_modifiers: &[],
+        damage_multiplier: 1.0,
+        damage: SpecDamage::DragonClaws,
+        hit_type: None,
+        effects: &[],
+    },
+    SpecialAttack {
+        weapon: "Burning claws",
+        name: "Searing Cleave",
+        energy_cost: 30,
+        accuracy_multiplier: 1.0,
+        accuracy_modifiers: &[],
+        damage_multiplier: 1.0,
+        damage: SpecDamage::BurningClaws,
+        hit_type: None,
+        effects: &[SpecEffect::Burn(0.15)],
+    },
+    SpecialAttack {
+        weapon: "Saradomin godsword",
+        name: "Healing Blade",
+        energy_cost: 50,
+        accuracy_multiplier: 2.0,
+        accuracy_modifiers: &[],
+        damage_multiplier: 1.1,
+        damage: SpecDamage::Standard,
+        hit_type: None,
+        effects: &[SpecEffect::Heal(0.5), SpecEffect::RestorePrayer(0.25)],
+    },
+    SpecialAttack {
+        weapon: "Bandos godsword",
+        name: "Warstrike",
+        energy_cost: 50,
+        accuracy_multiplier: 2.0,
+        accuracy_modifiers: &[],
+        damage_multiplier: 1.21,
+        damage: SpecDamage::Standard,
+        hit_type: None,
+        // Its defence drain by the damage dealt is not modelled
+        effects: &[],
+    },
+    SpecialAttack {
+        weapon: "Dragon warhammer",
+        name: "Smash",
+        energy_cost: 50,
+        accuracy_multiplier: 1.0,
+        accuracy_modifiers: &[],
+        damage_multiplier: 1.5,
+        damage: SpecDamage::Standard,
+        hit_type: None,
+        effects: &[SpecEffect::DrainDefence(0.3)],
+    },
+    SpecialAttack {
+        weapon: "Voidwaker",
+        name: "Disrupt",
+        energy_cost: 50,
+        accuracy_multiplier: 1.0,
+        accuracy_modifiers: &[AccuracyModifier::GuaranteedHit],
+        damage_multiplier: 1.5,
+        damage: SpecDamage::MinHit { min_percent: 50 },
+        hit_type: Some("magic"),
+        effects: &[],
+    },
+    SpecialAttack {
+        weapon: "Zaryte crossbow",
+        name: "Evoke",
+        energy_cost: 75,
+        accuracy_multiplier: 2.0,
+        accuracy_modifiers: &[],
+        damage_multiplier: 1.0,
+        damage: SpecDamage::Standard,
+        hit_type: None,
+        effects: &[SpecEffect::GuaranteedBoltEffect],
+    },
+    SpecialAttack {
+        weapon: "Armadyl crossbow",
+        name: "Armadyl Eye",
+        energy_cost: 50,
+        accuracy_multiplier: 2.0,
+        accuracy_modifiers: &[],
+        damage_multiplier: 1.0,
+        damage: SpecDamage::Standard,
+        hit_type: None,
+        effects: &[],
+    },
+    SpecialAttack {
+        weapon: "Toxic blowpipe",
+        name: "Toxic Siphon",
+        energy_cost: 50,
+        accuracy_multiplier: 1.0,
+        accuracy_modifiers: &[],
+        damage_multiplier: 1.5,
+        damage: SpecDamage::Standard,
+        hit_type: None,
+        effects: &[SpecEffect::Heal(0.5)],
+    },
+];
+
+/// The special attack of the weapon named `weapon`, if it has one catalogued.
+pub fn special_attack_for(weapon: &str) -> Option<&'static SpecialAttack> {
+    SPECIAL_ATTACKS.iter().find(|spec| spec.weapon.eq_ignore_ascii_case(weapon))
+}
+
+impl SpecialAttack {
+    /// Specs that `energy` pays for.
+    pub fn uses(&self, energy: u32) -> u32 {
+        energy / self.energy_cost.max(1)
+    }
+
+    /// Monster defence level after one draining hit at `level`; None when
+    /// the spec does not drain.
+    pub fn drained_defence(&self, level: u32) -> Option<u32> {
+        self.effects.iter().find_map(|effect| match effect {
+            SpecEffect::DrainDefence(fraction) => Some(level - (level as f64 * fraction).floor() as u32),
+            _ => None,
+        })
+    }
+
+    /// Damage of one spec for an unboosted `max_hit` and the spec's own hit chance.
+    pub fn hit_distribution(&self, max_hit: usize, accuracy: f64) -> HitDistribution {
+        let boosted = (max_hit as f64 * self.damage_multiplier).floor() as usize;
+        match self.damage {
+            SpecDamage::Standard => HitDistribution::uniform(boosted, accuracy),
+            SpecDamage::MinHit { min_percent } => {
+                let low = (max_hit * min_percent / 100).min(boosted);
+                let mut probs = vec![0.0; boosted + 1];
+                probs[0] = 1.0 - accuracy;
+                spread_uniform(&mut probs, accuracy, low, boosted, |x| x);
+                HitDistribution::from_pmf(probs)
+            }
+            SpecDamage::DoubleHit => {
+                let hit = HitDistribution::uniform(boosted, accuracy);
+                HitDistribution { pmf: hit.pmf.convolve(&hit.pmf) }
+            }
+            SpecDamage::DragonClaws => dragon_claws_spec(boosted, accuracy),
+            SpecDamage::BurningClaws => burning_claws_spec(boosted, accuracy),
+        }
+    }
+}
+
+/// One special attack as the player would land it on a monster.
+#[derive(Clone, Debug)]
+pub struct SpecEvaluation {
+    pub spec: &'static SpecialAttack,
+    pub accuracy: f64,
+    /// Max hit before the spec's damage multiplier.
+    pub max_hit: u32,
+    pub max_attack_roll: u64,
+    pub max_defence_roll: u64,
+    pub attack_speed: usize,
+    pub hit: HitDistribution,
+}
+
+impl SpecEvaluation {
+    pub fn expected_damage(&self) -> f64 {
+        self.hit.pmf.probs.iter().enumerate().map(|(damage, &p)| damage as f64 * p).sum()
+    }
+
+    /// Expected hitpoints healed per spec.
+    pub fn expected_heal(&self) -> f64 {
+        let fraction: f64 = self
+            .spec
+            .effects
+            .iter()
+            .map(|effect| match effect {
+                SpecEffect::Heal(fraction) => *fraction,
+                _ => 0.0,
+            })
+            .sum();
+        fraction * self.expected_damage()
+    }
+}
+
+/// `spec` made with the `combat_type` weapon in `style` against `monster`.
+///
+/// The rolls are those of a normal attack from `calculate_max_rolls_for_style`,
+/// with the attack and defence bonuses taken from the spec's hit type when it
+/// overrides the style's. The attack roll is then scaled by the spec's
+/// accuracy multiplier and its modifiers join the weapon's own.
+pub fn evaluate_spec(
+    player: &Player,
+    monster: &Monster,
+    combat_type: &str,
+    style: &WeaponStyle,
+    spec: &'static SpecialAttack,
+) -> Option<SpecEvaluation> {
+    let gear_set = gear_set_for(player, combat_type)?;
+    let weapon = gear_set.selected_weapon.as_ref()?;
+    let hit_style = match spec.hit_type {
+        Some(hit_type) => WeaponStyle { attack_type: hit_type.to_string(), ..style.clone() },
+        None => style.clone(),
+    };
+    let (attack_roll, max_defence_roll) =
+        calculate_max_rolls_for_style(player, monster, combat_type, &hit_style, &gear_set.gear_stats);
+    let max_attack_roll = (attack_roll as f64 * spec.accuracy_multiplier).floor() as u64;
+    let accuracy = AccuracyModel::new(max_attack_roll, max_defence_roll)
+        .with_modifiers(&accuracy_modifiers_for(weapon, &gear_set.gear_items))
+        .with_modifiers(spec.accuracy_modifiers)
+        .hit_chance();
+    let (max_hit, _) = calculate_max_hit_for_style(player, monster, combat_type, style, &gear_set.gear_stats);
+    Some(SpecEvaluation {
+        spec,
+        accuracy,
+        max_hit,
+        max_attack_roll,
+        max_defence_roll,
+        attack_speed: effective_attack_speed(weapon, combat_type, style),
+        hit: spec.hit_distribution(max_hit as usize, accuracy),
+    })
+}
+
+/// The spec of the `combat_type` weapon against `monster`, made in whichever
+/// of the weapon's styles deals the most expected damage; None when the
+/// weapon has no catalogued spec.
+pub fn best_spec(player: &Player, monster: &Monster, combat_type: &str) -> Option<SpecEvaluation> {
+    let weapon = gear_set_for(player, combat_type)?.selected_weapon.as_ref()?;
+    let spec = special_attack_for(&weapon.name)?;
+    weapon
+        .weapon_styles
+        .iter()
+        .flatten()
+        .filter_map(|style| evaluate_spec(player, monster, combat_type, style, spec))
+        .max_by(|a, b| a.expected_damage().total_cmp(&b.expected_damage()))
+}
+
+/// Spread `mass` evenly over rolls [low, high], crediting each roll's total damage.
+fn spread_uniform(probs: &mut Vec<f64>, mass: f64, low: usize, high: usize, total: impl Fn(usize) -> usize) {
+    let high = high.max(low);
+    let each = mass / (high - low + 1) as f64;
+    for roll in low..=high {
+        add_mass(probs, total(roll), each);
+    }
+}
+
+fn add_mass(probs: &mut Vec<f64>, damage: usize, mass: f64) {
+    if probs.len() <= damage {
+        probs.resize(damage + 1, 0.0);
+    }
+    probs[damage] += mass;
+}
+
+/// Drop damage values that cannot happen past the largest one that can.
+fn trim(mut probs: Vec<f64>) -> Vec<f64> {
+    let len = probs.iter().rposition(|&p| p > 0.0).map_or(1, |last| last + 1);
+    probs.truncate(len);
+    probs
+}
+
+fn catalogued(weapon: &str) -> &'static SpecialAttack {
+    special_attack_for(weapon).expect("spec is in SPECIAL_ATTACKS")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// PMF of a total from weighted outcomes: (probability, hitsplats).
+    fn enumerate(outcomes: impl IntoIterator<Item = (f64, Vec<usize>)>) -> Vec<f64> {
+        let mut probs = Vec::new();
+        for (p, hitsplats) in outcomes {
+            add_mass(&mut probs, hitsplats.iter().sum(), p);
+        }
+        trim(probs)
+    }
+
+    /// Each roll `x` of [low, high] with probability `p` spread over them, as hitsplats.
+    fn rolls(p: f64, low: usize, high: usize, hitsplats: impl Fn(usize) -> Vec<usize>) -> Vec<(f64, Vec<usize>)> {
+        (low..=high).map(|x| (p / (high - low + 1) as f64, hitsplats(x))).collect()
+    }
+
+    fn assert_pmf_eq(actual: &HitDistribution, expected: &[f64]) {
+        assert!((actual.pmf.mass() - 1.0).abs() < 1e-12, "mass {}", actual.pmf.mass());
+        assert_eq!(actual.pmf.len(), expected.len());
+        for (damage, (a, e)) in actual.pmf.probs.iter().zip(expected).enumerate() {
+            assert!((a - e).abs() < 1e-12, "P({damage}): {a} vs {e}");
+        }
+    }
+
+    #[test]
+    fn dragon_claws_match_the_hitsplat_cascade() {
+        for (max_hit, accuracy) in [(40, 0.6), (47, 0.35), (9, 0.9), (1, 0.5)] {
+            let miss = 1.0 - accuracy;
+            let mut outcomes = rolls(accuracy, max_hit / 2, max_hit.saturating_sub(1), |x| vec![x, x / 2, x / 4, x / 4 + 1]);
+            outcomes.extend(rolls(miss * accuracy, max_hit * 3 / 8, max_hit * 7 / 8, |x| vec![0, x, x / 2, x / 2 + 1]));
+            outcomes.extend(rolls(miss.powi(2) * accuracy, max_hit / 4, max_hit * 3 / 4, |x| vec![0, 0, x, x + 1]));
+            outcomes.extend(rolls(miss.powi(3) * accuracy, max_hit / 4, max_hit * 5 / 4, |x| vec![0, 0, 0, x]));
+            outcomes.push((miss.powi(4) * 2.0 / 3.0, vec![0, 0, 1, 1]));
+            outcomes.push((miss.powi(4) / 3.0, vec![0, 0, 0, 0]));
+            assert_pmf_eq(&dragon_claws_spec(max_hit, accuracy), &enumerate(outcomes));
+        }
+    }
+
+    #[test]
+    fn burning_claws_match_the_hitsplat_cascade() {
+        for (max_hit, accuracy) in [(40, 0.6), (47, 0.35), (9, 0.9)] {
+            let miss = 1.0 - accuracy;
+            let mut outcomes = rolls(accuracy, max_hit * 3 / 4, max_hit * 7 / 4, |x| vec![x]);
+            outcomes.extend(rolls(miss * accuracy, max_hit / 2, max_hit * 3 / 2, |x| vec![0, x]));
+            outcomes.extend(rolls(miss.powi(2) * accuracy, max_hit / 4, max_hit * 5 / 4, |x| vec![0, 0, x]));
+            outcomes.push((miss.powi(3), vec![0, 0, 0]));
+            assert_pmf_eq(&burning_claws_spec(max_hit, accuracy), &enumerate(outcomes));
+        }
+    }
+
+    #[test]
+    fn dragon_dagger_rolls_two_boosted_hits() {
+        let (max_hit, accuracy) = (30, 0.7);
+        let boosted = (max_hit as f64 * 1.15).floor() as usize;
+        let one_hit: Vec<(f64, usize)> = std::iter::once((1.0 - accuracy, 0))
+            .chain((0..=boosted).map(|damage| (accuracy / (boosted + 1) as f64, damage)))
+            .collect();
+        let outcomes = one_hit.iter().flat_map(|&(p1, d1)| one_hit.iter().map(move |&(p2, d2)| (p1 * p2, vec![d1, d2])));
+        assert_pmf_eq(&dragon_dagger_spec(max_hit, accuracy), &enumerate(outcomes));
+    }
+}
@@ -0,0 +1,322 @@
+use osrs_shared_types::*;
+
+use crate::distribution::convolve;
+
+/// Ticks an overload dose lasts inside the Chambers of Xeric.
+pub const OVERLOAD_DURATION_TICKS: usize = 500;
+
+/// Each Saradomin brew drains stats; one restore dose is sipped for every this many brew doses.
+const BREW_DOSES_PER_RESTORE: f64 = 3.0;
+
+/// Doses assumed for a potion whose name carries no "(n)" suffix.
+const DEFAULT_POTION_DOSES: u32 = 4;
+
+/// Prayer drain effect per tick: (prayer name, drain effect).
+const PRAYER_DRAIN_EFFECTS: &[(&str, f64)] = &[
+    ("piety", 24.0),
+    ("rigour", 24.0),
+    ("augury", 24.0),
+    ("eagle eye", 12.0),
+    ("mystic might", 12.0),
+    ("protect from melee", 12.0),
+    ("protect from missiles", 12.0),
+    ("protect from magic", 12.0),
+];
+
+/// Potion doses carried in the inventory.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SupplyInventory {
+    pub brew_doses: u32,
+    pub restore_doses: u32,
+    pub overload_doses: u32,
+}
+
+/// Prayers assumed to be active while attacking with `combat_type`.
+pub fn active_prayers(combat_type: &str, protection_prayer: Option<&str>) -> Vec<String> {
+    let mut prayers = Vec::new();
+    match combat_type {
+        "melee" => prayers.push("piety".to_string()),
+        "ranged" => prayers.push("rigour".to_string()),
+        "magic" => prayers.push("augury".to_string()),
+        _ => {}
+    }
+    match protection_prayer {
+        Some("melee") => prayers.push("protect from melee".to_string()),
+        Some("ranged") => prayers.push("protect from missiles".to_string()),
+        Some("magic") => prayers.push("protect from magic".to_string()),
+        _ => {}
+    }
+    prayers
+}
+
+/// Prayer points drained per tick with `prayers` active and `prayer_bonus` worn.
+pub fn prayer_drain_per_tick(prayers: &[String], prayer_bonus: i32) -> f64 {
+    let drain_effect: f64 = prayers
+        .iter()
+        .filter_map(|prayer| {
+            PRAYER_DRAIN_EFFECTS
+                .iter()
+                .find(|(name, _)| prayer.eq_ignore_ascii_case(name))
+                .map(|(_, effect)| *effect)
+        })
+        .sum();
+    let drain_resistance = 60.0 + 2.0 * prayer_bonus as f64;
+    drain_effect / drain_resistance
+}
+
+/// Doses from a potion name such as "Super restore(3)".
+fn potion_doses(name: &str) -> u32 {
+    name.rsplit_once('(')
+        .and_then(|(_, rest)| rest.strip_suffix(')'))
+        .and_then(|doses| doses.parse().ok())
+        .unwrap_or(DEFAULT_POTION_DOSES)
+}
+
+/// Count brew, restore and overload doses in the inventory.
+pub fn count_supply_doses(inventory: &[InventoryItem]) -> SupplyInventory {
+    let mut supplies = SupplyInventory::default();
+    for item in inventory {
+        let name = item.name.to_lowercase();
+        if name.contains("saradomin brew") {
+            supplies.brew_doses += potion_doses(&name);
+        } else if name.contains("super restore") || name.contains("prayer potion") {
+            supplies.restore_doses += potion_doses(&name);
+        } else if name.contains("overload") {
+            supplies.overload_doses += potion_doses(&name);
+        }
+    }
+    supplies
+}
+
+fn brew_doses_for(damage: usize, heal_per_dose: usize) -> u32 {
+    damage.div_ceil(heal_per_dose) as u32
+}
+
+fn restore_doses_for(drained: usize, prayer_level: f64, restore_per_dose: f64) -> u32 {
+    ((drained as f64 - prayer_level).max(0.0) / restore_per_dose).ceil() as u32
+}
+
+fn overload_doses_for(ticks: usize) -> u32 {
+    ticks.div_ceil(OVERLOAD_DURATION_TICKS) as u32
+}
+
+/// Supply needs of a room from the damage taken in it and its duration.
+///
+/// `duration_cdf` holds (tick, P(room done by tick)) pairs; mass beyond the
+/// last pair is put on it. Prayer drains at `drain_per_tick` for the whole
+/// room, rounded to whole points.
+pub fn room_supply_needs(drain_per_tick: f64, damage_pmf: &[f64], duration_cdf: &[(usize, f64)]) -> SupplyNeeds {
+    let mut ticks_pmf = vec![0.0; duration_cdf.last().map_or(1, |&(ticks, _)| ticks + 1)];
+    let mut drain_pmf = vec![0.0];
+    let mut prev_cdf = 0.0;
+    for (i, &(ticks, cdf)) in duration_cdf.iter().enumerate() {
+        let p = if i + 1 == duration_cdf.len() { 1.0 - prev_cdf } else { cdf - prev_cdf };
+        prev_cdf = cdf;
+        if p <= 0.0 {
+            continue;
+        }
+        ticks_pmf[ticks] += p;
+        let drained = (drain_per_tick * ticks as f64).round() as usize;
+        if drain_pmf.len() <= drained {
+            drain_pmf.resize(drained + 1, 0.0);
+        }
+        drain_pmf[drained] += p;
+    }
+    if duration_cdf.is_empty() {
+        ticks_pmf[0] = 1.0;
+        drain_pmf[0] = 1.0;
+    }
+    SupplyNeeds { damage_pmf: damage_pmf.to_vec(), drain_pmf, ticks_pmf }
+}
+
+/// Needs of rooms done one after another, each independent of the others.
+pub fn raid_supply_needs(rooms: &[SupplyNeeds]) -> SupplyNeeds {
+    rooms.iter().fold(SupplyNeeds { damage_pmf: vec![1.0], drain_pmf: vec![1.0], ticks_pmf: vec![1.0] }, |total, room| SupplyNeeds {
+        damage_pmf: convolve(&total.damage_pmf, &room.damage_pmf),
+        drain_pmf: convolve(&total.drain_pmf, &room.drain_pmf),
+        ticks_pmf: convolve(&total.ticks_pmf, &room.ticks_pmf),
+    })
+}
+
+/// Doses `doses(k)` over `pmf`, as a PMF over dose counts.
+fn dose_pmf(pmf: &[f64], doses: impl Fn(usize) -> u32) -> Vec<f64> {
+    let mut out = vec![0.0];
+    for (k, &p) in pmf.iter().enumerate() {
+        let doses = doses(k) as usize;
+        if out.len() <= doses {
+            out.resize(doses + 1, 0.0);
+        }
+        out[doses] += p;
+    }
+    out
+}
+
+fn expected(pmf: &[f64]) -> f64 {
+    pmf.iter().enumerate().map(|(k, &p)| k as f64 * p).sum()
+}
+
+/// P(more than `carried` doses are needed).
+fn p_beyond(pmf: &[f64], carried: u32) -> f64 {
+    pmf.iter().skip(carried as usize + 1).sum()
+}
+
+/// Expected supplies used against `needs` and the chance of running out.
+///
+/// The player starts at full prayer and heals every point of damage with
+/// brews, sipping a restore for every `BREW_DOSES_PER_RESTORE` brews on top
+/// of those for prayer. Restores are counted per scenario, treating damage
+/// taken and prayer drained as independent.
+pub fn supplies_for(player: &Player, needs: &SupplyNeeds) -> SupplyUsage {
+    let carried = count_supply_doses(&player.inventory);
+    let hitpoints = player.combat_stats.hitpoints as usize;
+    let prayer_level = player.combat_stats.prayer as f64;
+    let heal_per_dose = hitpoints * 15 / 100 + 2;
+    let restore_per_dose = (prayer_level / 4.0).floor() + 8.0;
+
+    let brews = dose_pmf(&needs.damage_pmf, |damage| brew_doses_for(damage, heal_per_dose));
+    let restores_for_brews = dose_pmf(&brews, |doses| (doses as f64 / BREW_DOSES_PER_RESTORE).ceil() as u32);
+    let restores_for_prayer = dose_pmf(&needs.drain_pmf, |drained| restore_doses_for(drained, prayer_level, restore_per_dose));
+    let restores = convolve(&restores_for_brews, &restores_for_prayer);
+    let overloads = dose_pmf(&needs.ticks_pmf, overload_doses_for);
+
+    SupplyUsage {
+        prayer_points_drained: expected(&needs.drain_pmf),
+        brew_doses: expected(&brews),
+        restore_doses: expected(&restores),
+        overload_doses: expected(&overloads),
+        p_out_of_brews: p_beyond(&brews, carried.brew_doses),
+        p_out_of_restores: p_beyond(&restores, carried.restore_doses),
+        p_out_of_overloads: p_beyond(&overloads, carried.overload_doses),
+    }
+}
+
+/// Expected supplies used in a room and the chance of running out; see
+/// `room_supply_needs` and `supplies_for`.
+pub fn estimate_supplies(
+    player: &Player,
+    drain_per_tick: f64,
+    damage_pmf: &[f64],
+    duration_cdf: &[(usize, f64)],
+) -> SupplyUsage {
+    supplies_for(player, &room_supply_needs(drain_per_tick, damage_pmf, duration_cdf))
+}
+
+/// Supplies for a whole raid from each room's `supply_needs`: the rooms'
+/// damage, drain and duration add up, so every run-dry chance is for the
+/// raid as a whole on one inventory, with no prayer restored between rooms.
+pub fn raid_supplies(player: &Player, rooms: &[SupplyNeeds]) -> SupplyUsage {
+    supplies_for(player, &raid_supply_needs(rooms))
+}
+
+/// `raid_supplies` for a JSON `RaidSuppliesPayload`, as the room modules export it.
+pub fn raid_supplies_json(payload_json: &str) -> String {
+    match serde_json::from_str::<RaidSuppliesPayload>(payload_json) {
+        Ok(payload) => serde_json::json!({ "supplies": raid_supplies(&payload.player, &payload.rooms) }).to_string(),
+        Err(e) => format!("{{\"error\": \"Failed to parse payload data: {}\"}}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    /// A 99-all player carrying `items` (99 hitpoints heal 16 per brew dose,
+    /// 99 prayer restores 32 per dose).
+    fn player_with(items: &[&str]) -> Player {
+        let mut player = test_fixtures::player("Dragon claws", 4, &[]);
+        player.inventory = items.iter().map(|name| InventoryItem { name: name.to_string(), equipment: None }).collect();
+        player
+    }
+
+    /// A PMF with `p` at each `(k, p)`.
+    fn pmf(points: &[(usize, f64)]) -> Vec<f64> {
+        let mut pmf = vec![0.0; points.iter().map(|&(k, _)| k + 1).max().unwrap_or(1)];
+        for &(k, p) in points {
+            pmf[k] += p;
+        }
+        pmf
+    }
+
+    fn needs(damage: &[(usize, f64)], drained: &[(usize, f64)]) -> SupplyNeeds {
+        SupplyNeeds { damage_pmf: pmf(damage), drain_pmf: pmf(drained), ticks_pmf: vec![1.0] }
+    }
+
+    #[test]
+    fn potion_doses_read_the_suffix() {
+        assert_eq!(potion_doses("super restore(3)"), 3);
+        assert_eq!(potion_doses("overload (+)(1)"), 1);
+        assert_eq!(potion_doses("saradomin brew"), DEFAULT_POTION_DOSES);
+        assert_eq!(potion_doses("prayer potion(x)"), DEFAULT_POTION_DOSES);
+    }
+
+    #[test]
+    fn supply_doses_are_counted_by_potion() {
+        let carried = count_supply_doses(&player_with(&[
+            "Saradomin brew(4)",
+            "Saradomin brew(2)",
+            "Super restore(3)",
+            "Prayer potion(4)",
+            "Overload (+)(2)",
+            "Shark",
+        ]).inventory);
+        assert_eq!((carried.brew_doses, carried.restore_doses, carried.overload_doses), (6, 7, 2));
+    }
+
+    #[test]
+    fn prayer_drain_follows_drain_resistance() {
+        let prayers = active_prayers("melee", Some("melee"));
+        assert!(close(prayer_drain_per_tick(&prayers, 0), 36.0 / 60.0));
+        assert!(close(prayer_drain_per_tick(&prayers, 15), 36.0 / 90.0));
+        assert!(close(prayer_drain_per_tick(&["PIETY".to_string()], 0), 24.0 / 60.0));
+        assert!(close(prayer_drain_per_tick(&["thick skin".to_string()], 0), 0.0));
+    }
+
+    #[test]
+    fn room_needs_take_ticks_and_drain_from_the_duration_cdf() {
+        let needs = room_supply_needs(2.0, &[1.0], &[(0, 0.0), (1, 0.5), (2, 0.8)]);
+        assert_eq!(needs.ticks_pmf.len(), 3);
+        assert!(close(needs.ticks_pmf[1], 0.5) && close(needs.ticks_pmf[2], 0.5));
+        assert_eq!(needs.drain_pmf.len(), 5);
+        assert!(close(needs.drain_pmf[2], 0.5) && close(needs.drain_pmf[4], 0.5));
+    }
+
+    #[test]
+    fn brew_restores_are_counted_per_damage_scenario() {
+        // Half the time no damage, half the time 48 (three brews, one restore):
+        // one restore in expectation would be the old, fixed-amount answer.
+        let usage = supplies_for(&player_with(&[]), &needs(&[(0, 0.5), (48, 0.5)], &[(0, 1.0)]));
+        assert!(close(usage.brew_doses, 1.5));
+        assert!(close(usage.restore_doses, 0.5));
+        assert!(close(usage.p_out_of_restores, 0.5));
+    }
+
+    #[test]
+    fn restores_for_brews_and_prayer_add_up() {
+        // 48 damage takes one restore for its brews; 100 points drained from
+        // 99 prayer takes another.
+        let usage = supplies_for(&player_with(&["Super restore(1)"]), &needs(&[(48, 1.0)], &[(100, 1.0)]));
+        assert!(close(usage.restore_doses, 2.0));
+        assert!(close(usage.p_out_of_restores, 1.0));
+    }
+
+    #[test]
+    fn raid_running_dry_is_at_least_as_likely_as_any_room() {
+        let player = player_with(&["Saradomin brew(4)"]);
+        let room = needs(&[(0, 0.5), (48, 0.5)], &[(0, 1.0)]);
+        assert!(close(supplies_for(&player, &room).p_out_of_brews, 0.0));
+
+        let raid = raid_supplies(&player, &[room.clone(), room]);
+        assert!(close(raid.brew_doses, 3.0));
+        assert!(close(raid.p_out_of_brews, 0.25));
+    }
+
+    #[test]
+    fn raid_supplies_json_reports_parse_errors() {
+        assert!(raid_supplies_json("{}").contains("error"));
+    }
+}
@@ -0,0 +1,83 @@
+//! Payload pieces for room-level tests, built the way the frontend sends them.
+use osrs_shared_types::*;
+use serde_json::{json, Value};
+
+fn gear_stats(str_bonus: i32, slash: i32) -> Value {
+    json!({
+        "bonuses": { "str": str_bonus, "ranged_str": 0, "magic_str": 0, "prayer": 0 },
+        "offensive": { "stab": 0, "slash": slash, "crush": 0, "magic": 0, "ranged": 0 },
+        "defensive": { "stab": 0, "slash": 0, "crush": 0, "magic": 0, "ranged": 0 }
+    })
+}
+
+fn item(name: &str, slot: &str) -> Value {
+    json!({ "name": name, "id": 0, "speed": 0, "two_handed": false, "slot": slot, "category": "", "weapon_styles": null })
+}
+
+fn weapon_json(name: &str, category: &str, speed: i32, attack_type: &str) -> Value {
+    json!({
+        "name": name, "id": 0, "speed": speed, "two_handed": false, "slot": "weapon", "category": category,
+        "weapon_styles": [{
+            "name": attack_type, "attack_type": attack_type, "combat_style": "Aggressive",
+            "att": 0, "str": 3, "def": 0, "ranged": 0, "magic": 0, "att_spd_reduction": 0
+        }]
+    })
+}
+
+/// A weapon with a single Aggressive style of `attack_type` ("Slash", "Magic", ...).
+pub(crate) fn weapon(name: &str, category: &str, speed: i32, attack_type: &str) -> SelectedItem {
+    serde_json::from_value(weapon_json(name, category, speed, attack_type)).unwrap()
+}
+
+/// A worn item such as "Salve amulet(ei)".
+pub(crate) fn worn(name: &str) -> SelectedItem {
+    serde_json::from_value(item(name, "ring")).unwrap()
+}
+
+/// A 99-all player with a melee `weapon` (one slash style) and `worn` items
+/// in the melee set; the ranged and magic sets are empty.
+pub(crate) fn player(weapon: &str, speed: i32, worn: &[&str]) -> Player {
+    let empty_set = |gear_type: &str| json!({ "gearStats": gear_stats(0, 0), "selectedWeapon": null, "gearType": gear_type, "gearItems": [] });
+    let weapon = weapon_json(weapon, "Claw", speed, "Slash");
+    let gear_items: Vec<Value> = worn.iter().map(|name| item(name, "ring")).collect();
+    serde_json::from_value(json!({
+        "combatStats": {
+            "attack": 99, "strength": 99, "defense": 99, "ranged": 99, "magic": 99,
+            "hitpoints": 99, "prayer": 99, "woodcutting": 99, "mining": 99, "thieving": 99
+        },
+        "gearSets": {
+            "melee": { "gearStats": gear_stats(100, 100), "selectedWeapon": weapon, "gearType": "melee", "gearItems": gear_items },
+            "mage": empty_set("mage"),
+            "ranged": empty_set("ranged")
+        },
+        "inventory": []
+    }))
+    .unwrap()
+}
+
+/// A monster with `hp`, 50 defence, a 10 max hit melee attack every 4 ticks
+/// and the given `immunities` (e.g. `{"burn": true}`).
+pub(crate) fn monster(name: &str, hp: u32, immunities: Value) -> Monster {
+    serde_json::from_value(json!({
+        "id": 1, "name": name, "version": null, "image": null, "level": 100, "speed": 4,
+        "style": ["slash"], "size": 1, "max_hit": 10,
+        "skills": { "atk": 50, "def": 50, "hp": hp, "magic": 50, "ranged": 50, "str": 50 },
+        "offensive": { "ranged_str": 0, "magic_str": 0, "atk": 0, "magic": 0, "ranged": 0, "str": 0 },
+        "defensive": { "flat_armour": 0, "crush": 0, "magic": 0, "heavy": 0, "standard": 0, "light": 0, "slash": 0, "stab": 0 },
+        "attributes": null, "immunities": immunities, "weakness": null
+    }))
+    .unwrap()
+}
+
+pub(crate) fn room(monsters: Vec<Monster>) -> Room {
+    Room { id: "test".to_string(), name: "Test room".to_string(), image: None, description: None, monsters, distance: None }
+}
+
+/// A config capped at 0.999 with the fields of `extra` set on top.
+pub(crate) fn config(extra: Value) -> DPSConfig {
+    let mut config = json!({ "cap": 0.999 });
+    if let (Some(config), Some(extra)) = (config.as_object_mut(), extra.as_object()) {
+        config.extend(extra.clone());
+    }
+    serde_json::from_value(config).unwrap()
+}
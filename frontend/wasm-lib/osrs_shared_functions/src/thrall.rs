@@ -0,0 +1,177 @@
+use osrs_shared_types::*;
+
+use crate::schedule::{Attacker, HitDistribution};
+
+/// Ticks between thrall attacks.
+pub const THRALL_ATTACK_SPEED: usize = 4;
+
+/// Ticks from the cast to the thrall's first attack.
+const THRALL_SPAWN_DELAY: usize = 2;
+
+/// Ticks before another thrall can be summoned (10 seconds).
+const THRALL_COOLDOWN_TICKS: usize = 17;
+
+/// Max hit by tier: (tier, max hit).
+const THRALL_TIERS: &[(&str, usize)] = &[("lesser", 1), ("superior", 2), ("greater", 3)];
+
+/// Attack style by kind: (kind, style).
+const THRALL_KINDS: &[(&str, &str)] = &[("ghost", "magic"), ("skeleton", "ranged"), ("zombie", "melee")];
+
+/// A summoned Arceuus thrall. Thralls never miss; each attack deals uniform
+/// damage on [0, max_hit].
+#[derive(Clone, Debug)]
+pub struct Thrall {
+    pub name: String,
+    pub max_hit: usize,
+    pub style: &'static str,
+    pub cast_tick: usize,
+    /// Ticks a cast lasts: one tick per Magic level.
+    pub duration: usize,
+    pub recast: bool,
+}
+
+impl Thrall {
+    /// Look up a thrall such as "Greater ghost"; an error for an unknown name.
+    pub fn from_config(config: &ThrallConfig, magic_level: u32) -> Result<Self, String> {
+        let name = config.name.to_lowercase();
+        let unknown = || format!("unknown thrall \"{}\"", config.name);
+        let (_, max_hit) = THRALL_TIERS.iter().find(|(tier, _)| name.contains(tier)).ok_or_else(unknown)?;
+        let (_, style) = THRALL_KINDS.iter().find(|(kind, _)| name.contains(kind)).ok_or_else(unknown)?;
+        Ok(Thrall {
+            name: config.name.clone(),
+            max_hit: *max_hit,
+            style,
+            cast_tick: config.cast_tick,
+            duration: magic_level as usize,
+            recast: config.recast.unwrap_or(true),
+        })
+    }
+
+    pub fn hit_distribution(&self) -> HitDistribution {
+        HitDistribution::uniform(self.max_hit, 1.0)
+    }
+
+    /// [cast, expiry) of every cast starting before `horizon`, in room ticks.
+    fn active_windows(&self, horizon: usize) -> Vec<(usize, usize)> {
+        let mut windows = Vec::new();
+        let mut cast = self.cast_tick;
+        while cast < horizon && self.duration > 0 {
+            windows.push((cast, cast + self.duration));
+            if !self.recast {
+                break;
+            }
+            cast = (cast + self.duration).max(cast + THRALL_COOLDOWN_TICKS);
+        }
+        windows
+    }
+
+    /// The thrall's attacks during a fight starting at room tick `fight_start`,
+    /// one attacker per cast, in ticks relative to the fight start.
+    pub fn attackers(&self, fight_start: usize, horizon: usize) -> Vec<Attacker> {
+        self.active_windows(fight_start + horizon)
+            .into_iter()
+            .filter_map(|(cast, expiry)| {
+                let mut first = cast + THRALL_SPAWN_DELAY;
+                if first < fight_start {
+                    first += (fight_start - first).div_ceil(THRALL_ATTACK_SPEED) * THRALL_ATTACK_SPEED;
+                }
+                (first < expiry).then(|| {
+                    Attacker::new(&self.name, self.hit_distribution(), THRALL_ATTACK_SPEED)
+                        .with_first_attack_tick(first - fight_start)
+                        .until(expiry - 1 - fight_start)
+                })
+            })
+            .collect()
+    }
+}
+
+/// The thrall configured for the room, cast by `player`; an error for an
+/// unknown thrall name.
+pub fn configured_thrall(config: &DPSConfig, player: &Player) -> Result<Option<Thrall>, String> {
+    config
+        .thrall
+        .as_ref()
+        .map(|thrall| Thrall::from_config(thrall, player.combat_stats.magic))
+        .transpose()
+}
+
+/// The room's thrall, unless the monster is immune to thralls.
+pub fn thrall_for(thrall: Option<&Thrall>, monster: &Monster) -> Option<Thrall> {
+    thrall.filter(|_| !monster_is_immune(monster, "thrall")).cloned()
+}
+
+/// Whether the monster's `immunities` mark it immune to `effect` ("burn", "poison", "thrall", ...).
+pub fn monster_is_immune(monster: &Monster, effect: &str) -> bool {
+    let Some(value) = monster.immunities.as_ref().and_then(|immunities| immunities.get(effect)) else {
+        return false;
+    };
+    match value {
+        serde_json::Value::Bool(immune) => *immune,
+        serde_json::Value::String(status) => status.eq_ignore_ascii_case("immune"),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+    use serde_json::json;
+
+    fn thrall(name: &str, magic_level: u32, recast: bool) -> Result<Thrall, String> {
+        Thrall::from_config(&ThrallConfig { name: name.to_string(), cast_tick: 0, recast: Some(recast) }, magic_level)
+    }
+
+    #[test]
+    fn tiers_set_the_max_hit_and_kinds_the_style() {
+        for (name, max_hit, style) in [("Lesser zombie", 1, "melee"), ("Superior skeleton", 2, "ranged"), ("Greater ghost", 3, "magic")] {
+            let thrall = thrall(name, 99, true).unwrap();
+            assert_eq!((thrall.max_hit, thrall.style), (max_hit, style), "{name}");
+            // Thralls never miss: every damage from 0 to the max hit is equally likely
+            let pmf = &thrall.hit_distribution().pmf.probs;
+            assert_eq!(pmf.len(), max_hit + 1);
+            assert!(pmf.iter().all(|&p| (p - 1.0 / (max_hit + 1) as f64).abs() < 1e-12));
+        }
+    }
+
+    #[test]
+    fn unknown_thrall_names_are_errors() {
+        assert!(thrall("Greater goblin", 99, true).unwrap_err().contains("Greater goblin"));
+        assert!(thrall("Ghost", 99, true).is_err());
+
+        let config = test_fixtures::config(json!({ "thrall": { "name": "Greater gohst" } }));
+        let party = [test_fixtures::player("Dragon scimitar", 4, &[])];
+        assert!(configured_thrall(&config, &party[0]).is_err());
+        let room = test_fixtures::room(vec![test_fixtures::monster("Target", 50, json!({}))]);
+        let rules = crate::RoomRules { combat_types: &["melee"], tick_model: crate::TickModel::new(0) };
+        let result = crate::simulate_room(&party, &room, &config, &rules);
+        assert!(result["error"].as_str().unwrap().contains("unknown thrall"));
+    }
+
+    #[test]
+    fn a_cast_lasts_a_tick_per_magic_level() {
+        let thrall = thrall("Greater ghost", 90, false).unwrap();
+        assert_eq!(thrall.active_windows(1000), [(0, 90)]);
+        let attackers = thrall.attackers(0, 1000);
+        assert_eq!(attackers.len(), 1);
+        assert_eq!(attackers[0].first_attack_tick, THRALL_SPAWN_DELAY);
+        assert_eq!(attackers[0].last_attack_tick, Some(89));
+    }
+
+    #[test]
+    fn recasts_wait_for_expiry_and_the_cooldown() {
+        // A 99 cast outlasts the cooldown, so it is recast the tick it expires
+        assert_eq!(thrall("Greater ghost", 99, true).unwrap().active_windows(250), [(0, 99), (99, 198), (198, 297)]);
+        // A 10 tick cast expires first and waits out the 17 tick cooldown
+        assert_eq!(thrall("Greater ghost", 10, true).unwrap().active_windows(40), [(0, 10), (17, 27), (34, 44)]);
+    }
+
+    #[test]
+    fn fights_joining_late_pick_up_the_attack_cycle() {
+        let thrall = thrall("Greater ghost", 99, false).unwrap();
+        // Attacks land on 2, 6, 10, ...; a fight from tick 5 first sees the one on 6
+        let attackers = thrall.attackers(5, 100);
+        assert_eq!(attackers[0].first_attack_tick, 1);
+        assert_eq!(attackers[0].last_attack_tick, Some(98 - 5));
+    }
+}
@@ -0,0 +1,201 @@
+use crate::distribution::{Cdf, Pmf};
+
+/// Round `ticks` up to the next multiple of `cycle`.
+pub fn align_to_cycle(ticks: f64, cycle: usize) -> f64 {
+    if cycle <= 1 {
+        return ticks;
+    }
+    let cycle = cycle as f64;
+    (ticks / cycle).ceil() * cycle
+}
+
+/// Phases in which a room's monsters cannot be attacked, such as Tekton's
+/// trips to the anvil: after every `active_ticks` of fighting they are out of
+/// reach for `downtime_ticks`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RoomDowntime {
+    pub active_ticks: usize,
+    pub downtime_ticks: usize,
+}
+
+/// How a room's timeline is laid out in ticks. Each room crate sets its own.
+#[derive(Clone, Copy, Debug)]
+pub struct TickModel {
+    /// Tick of the player's first attack, counted from the room start (walk-in, setup).
+    pub first_attack_tick: usize,
+    /// Completion is rounded up to a multiple of this many ticks (1 = no alignment).
+    pub alignment_cycle: usize,
+    /// Ticks after a kill lands before the next target can be attacked.
+    pub retarget_delay: usize,
+    /// Phases without fighting, counted from the room start; none when absent.
+    pub downtime: Option<RoomDowntime>,
+}
+
+impl TickModel {
+    pub const fn new(first_attack_tick: usize) -> Self {
+        TickModel {
+            first_attack_tick,
+            alignment_cycle: 1,
+            retarget_delay: 0,
+            downtime: None,
+        }
+    }
+
+    pub const fn with_alignment_cycle(self, alignment_cycle: usize) -> Self {
+        TickModel { alignment_cycle, ..self }
+    }
+
+    pub const fn with_downtime(self, active_ticks: usize, downtime_ticks: usize) -> Self {
+        TickModel { downtime: Some(RoomDowntime { active_ticks, downtime_ticks }), ..self }
+    }
+
+    pub const fn with_retarget_delay(self, retarget_delay: usize) -> Self {
+        TickModel { retarget_delay, ..self }
+    }
+
+    /// Ticks from a kill landing to the first attack on the next target.
+    ///
+    /// The attack timer carries over between targets, so the next attack is no
+    /// sooner than `attack_speed` ticks after the killing attack, nor sooner
+    /// than `retarget_delay` ticks after its hitsplat lands.
+    pub fn handoff_ticks(&self, attack_speed: usize, hit_delay: usize) -> usize {
+        attack_speed.max(hit_delay + self.retarget_delay) - hit_delay
+    }
+
+    /// Room tick at which fighting time `ticks` is reached: every downtime
+    /// phase started before the last tick of fighting is sat out.
+    fn with_downtime_sat_out(&self, ticks: f64) -> f64 {
+        match self.downtime {
+            Some(RoomDowntime { active_ticks, downtime_ticks }) if active_ticks > 0 => {
+                let phases = ((ticks - 1.0).max(0.0) / active_ticks as f64).floor();
+                ticks + phases * downtime_ticks as f64
+            }
+            _ => ticks,
+        }
+    }
+
+    /// Completion tick of a room whose last kill lands on fighting tick `ticks`:
+    /// downtime sat out, then rounded up to the next cycle boundary.
+    pub fn completion_tick(&self, ticks: usize) -> usize {
+        align_to_cycle(self.with_downtime_sat_out(ticks as f64), self.alignment_cycle) as usize
+    }
+
+    /// Expected aligned completion tick of a completion tick distributed as
+    /// `pmf`, whose exact mean is `expected_ticks`: the mean plus the expected
+    /// delay from downtime and the round-up to the next cycle boundary, which
+    /// is E[aligned T] rather than the aligned mean.
+    pub fn expected_aligned(&self, pmf: &Pmf, expected_ticks: f64) -> f64 {
+        let mass: f64 = pmf.probs.iter().sum();
+        if mass <= 0.0 {
+            return align_to_cycle(self.with_downtime_sat_out(expected_ticks), self.alignment_cycle);
+        }
+        let delay: f64 = pmf
+            .probs
+            .iter()
+            .enumerate()
+            .map(|(t, &p)| p * (self.completion_tick(t) - t) as f64)
+            .sum();
+        expected_ticks + delay / mass
+    }
+
+    /// CDF of the aligned completion tick. Completion ticks never decrease
+    /// with the fighting tick, so P(completed by c) = P(T <= last t completing by c).
+    pub fn align_cdf(&self, cdf: &Cdf) -> Cdf {
+        // Runs to the tick the last tick of `cdf` completes on
+        let len = self.completion_tick(cdf.probs.len().saturating_sub(1)) + 1;
+        let mut probs = vec![0.0; len];
+        for (t, &p) in cdf.probs.iter().enumerate() {
+            probs[self.completion_tick(t)] = p;
+        }
+        for c in 1..len {
+            probs[c] = probs[c].max(probs[c - 1]);
+        }
+        Cdf::new(probs)
+    }
+}
+
+/// Completion time of a room's monsters killed one after another, each kill
+/// given as a per-tick CDF from the first attack on that monster.
+///
+/// Kills may use different weapons; every distribution is already in ticks,
+/// so they combine by convolution with the hand-off between targets added in.
+#[derive(Clone, Debug)]
+pub struct EncounterTimeline {
+    tick_model: TickModel,
+    pmf: Pmf,
+    expected_ticks: f64,
+    pending_handoff: Option<usize>,
+}
+
+impl EncounterTimeline {
+    pub fn new(tick_model: TickModel) -> Self {
+        EncounterTimeline {
+            tick_model,
+            pmf: Pmf::point(tick_model.first_attack_tick),
+            expected_ticks: tick_model.first_attack_tick as f64,
+            pending_handoff: None,
+        }
+    }
+
+    /// Append a kill taking `kill_cdf` (expected `expected_ticks`) with a weapon
+    /// of `attack_speed` whose hitsplats land `hit_delay` ticks after the attack.
+    pub fn add_kill(&mut self, kill_cdf: &Cdf, expected_ticks: f64, attack_speed: usize, hit_delay: usize) {
+        if let Some(handoff) = self.pending_handoff {
+            self.pmf = self.pmf.shift(handoff);
+            self.expected_ticks += handoff as f64;
+        }
+        self.pmf = self.pmf.convolve(&Pmf::from_cdf(kill_cdf));
+        self.expected_ticks += expected_ticks;
+        self.pending_handoff = Some(self.tick_model.handoff_ticks(attack_speed, hit_delay));
+    }
+
+    /// Expected room tick of the first attack on the next monster.
+    pub fn next_fight_start(&self) -> f64 {
+        self.expected_ticks + self.pending_handoff.unwrap_or(0) as f64
+    }
+
+    /// Expected aligned completion tick: the mean of `cdf`, with the
+    /// exact unaligned mean standing in for the tail the CDF was cut at.
+    pub fn expected_ticks(&self) -> f64 {
+        self.tick_model.expected_aligned(&self.pmf, self.expected_ticks)
+    }
+
+    /// Per-tick CDF of the aligned completion tick, from the room start.
+    pub fn cdf(&self) -> Cdf {
+        self.tick_model.align_cdf(&self.pmf.to_cdf())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expected_ticks_is_mean_of_aligned_cdf() {
+        let tick_model = TickModel::new(2).with_alignment_cycle(4);
+        let kill = Pmf::new(vec![0.0, 0.25, 0.25, 0.25, 0.25]);
+        let mut timeline = EncounterTimeline::new(tick_model);
+        timeline.add_kill(&kill.to_cdf(), 2.5, 4, 0);
+        let aligned_mean: f64 = Pmf::from_cdf(&timeline.cdf()).probs.iter().enumerate().map(|(t, &p)| t as f64 * p).sum();
+        assert!((timeline.expected_ticks() - aligned_mean).abs() < 1e-12);
+        // Completion on ticks 3..=6 aligns to 4, 4, 8, 8; the mean of 4.5 would align to 8
+        assert!((timeline.expected_ticks() - 6.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn downtime_is_sat_out_before_the_cycle_round_up() {
+        let tick_model = TickModel::new(0).with_alignment_cycle(4).with_downtime(10, 5);
+        // Fighting ticks 1..=10 need no downtime; 11..=20 sit one out
+        assert_eq!(tick_model.completion_tick(10), 12);
+        assert_eq!(tick_model.completion_tick(11), 16);
+        assert_eq!(tick_model.completion_tick(21), 32);
+
+        let kill = Pmf::new(vec![0.0; 10].into_iter().chain([0.5, 0.5]).collect());
+        let mut timeline = EncounterTimeline::new(tick_model);
+        timeline.add_kill(&kill.to_cdf(), 10.5, 4, 0);
+        let aligned = Pmf::from_cdf(&timeline.cdf());
+        assert_eq!(aligned.probs.iter().position(|&p| p > 0.0), Some(12));
+        assert!((aligned.probs[12] - 0.5).abs() < 1e-12 && (aligned.probs[16] - 0.5).abs() < 1e-12);
+        assert!((timeline.expected_ticks() - 14.0).abs() < 1e-12);
+    }
+}
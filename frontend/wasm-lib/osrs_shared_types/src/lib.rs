@@ -117,9 +117,41 @@ pub struct Player {
     #[serde(rename = "gearSets")]
     pub gear_sets: AllGearSets,
     pub inventory: Vec<InventoryItem>,
+    /// When this player attacks in a room; attacks on cooldown from the start when absent.
+    #[serde(default)]
+    pub schedule: PlayerSchedule,
 }
 
-#[derive(Deserialize)]
+/// One player's attack timing in a room. Ticks are counted from the room start.
+#[derive(Deserialize, Clone, Default)]
+pub struct PlayerSchedule {
+    /// Ticks after the party's first attack on each monster before this player's first attack.
+    #[serde(default)]
+    pub first_attack_delay: usize,
+    /// Gear set changes, in any order.
+    #[serde(default)]
+    pub switches: Vec<GearSwitchConfig>,
+    /// Windows in which the player does not attack.
+    #[serde(default)]
+    pub downtime: Vec<DowntimeConfig>,
+}
+
+/// From `tick` on, attack with the best style of the `combat_type` gear set.
+#[derive(Deserialize, Clone)]
+pub struct GearSwitchConfig {
+    pub tick: usize,
+    /// "melee", "ranged" or "magic".
+    pub combat_type: String,
+}
+
+/// Ticks in [start, end) during which the player cannot attack.
+#[derive(Deserialize, Clone, Copy)]
+pub struct DowntimeConfig {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Deserialize, Clone)]
 pub struct MonsterSkills {
     pub atk: u32,
     pub def: u32,
@@ -129,7 +161,7 @@ pub struct MonsterSkills {
     pub str: u32,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
 pub struct MonsterOffensive {
     #[serde(rename = "ranged_str")]
     pub ranged_str: i32,
@@ -141,7 +173,7 @@ pub struct MonsterOffensive {
     pub str: i32,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
 pub struct MonsterDefensive {
     pub flat_armour: i32,
     pub crush: i32,
@@ -153,7 +185,7 @@ pub struct MonsterDefensive {
     pub stab: i32,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
 pub struct Monster {
     pub id: u32,
     pub name: String,
@@ -170,6 +202,29 @@ pub struct Monster {
     pub attributes: Option<Vec<String>>,
     pub immunities: Option<serde_json::Value>,
     pub weakness: Option<serde_json::Value>,
+    #[serde(default)]
+    pub distance: Option<u32>,
+    /// HP the monster restores during the fight.
+    #[serde(default)]
+    pub heals: Vec<MonsterHeal>,
+}
+
+/// A heal from monster data: `amount` HP on `first_tick` (counted from the
+/// first attack on the monster), repeated every `interval` ticks until
+/// `last_tick`, only while HP is within [`min_hp`, `max_hp`].
+#[derive(Deserialize, Clone)]
+pub struct MonsterHeal {
+    pub amount: u32,
+    #[serde(default)]
+    pub first_tick: usize,
+    #[serde(default)]
+    pub interval: Option<usize>,
+    #[serde(default)]
+    pub last_tick: Option<usize>,
+    #[serde(default)]
+    pub min_hp: Option<u32>,
+    #[serde(default)]
+    pub max_hp: Option<u32>,
 }
 
 #[derive(Serialize)]
@@ -184,6 +239,7 @@ pub struct CalculationResult {
 
 #[derive(Serialize)]
 pub struct StyleResult {
+    pub combat_type: String,
     pub combat_style: String,
     pub attack_type: String,
     pub max_hit: u32,
@@ -194,6 +250,146 @@ pub struct StyleResult {
     pub max_attack_roll: u64,
     pub max_defence_roll: u64,
     pub att_spd_reduction: i32,
+    pub attack_speed: usize,
+}
+
+#[derive(Serialize)]
+pub struct DamageTaken {
+    pub expected_damage: f64,
+    pub expected_hp_lost: f64,
+    pub p_death: f64,
+    pub hp_lost_distribution: Vec<f64>,
+}
+
+#[derive(Serialize)]
+pub struct QuantilePoint {
+    pub p: f64,
+    pub tick: Option<usize>,
+}
+
+/// Quantiles of a kill-time distribution, in ticks.
+#[derive(Serialize)]
+pub struct KillTimeSummary {
+    pub p10: Option<usize>,
+    pub p50: Option<usize>,
+    pub p90: Option<usize>,
+    pub p99: Option<usize>,
+    pub quantiles: Vec<QuantilePoint>,
+    pub target: Option<usize>,
+    pub p_within_target: Option<f64>,
+}
+
+/// Damage wasted on the killing blow.
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct Overkill {
+    /// Expected damage rolled beyond the monster's remaining HP.
+    pub expected_overkill: f64,
+    /// Expected attacks made while the monster is within one max hit of dying.
+    pub expected_finishing_attacks: f64,
+    /// `final_hit_distribution[k]` = P(the killing attack rolled k).
+    pub final_hit_distribution: Vec<f64>,
+}
+
+/// Remaining monster HP at one tick; 0 HP means dead.
+#[derive(Serialize, Clone, Debug)]
+pub struct HpBand {
+    pub tick: usize,
+    pub expected_hp: f64,
+    pub p10: usize,
+    pub p50: usize,
+    pub p90: usize,
+}
+
+/// Full remaining-HP distribution at a tick: `distribution[h]` = P(h HP left).
+#[derive(Serialize, Clone, Debug)]
+pub struct HpSnapshot {
+    pub tick: usize,
+    pub distribution: Vec<f64>,
+}
+
+/// Whether a kill-time distribution reached its cap within the tick budget.
+/// `killable` is false when no attack can ever do damage; `residual_mass` is
+/// the probability the distribution never placed.
+#[derive(Serialize, Clone, Copy)]
+pub struct Convergence {
+    pub killable: bool,
+    pub truncated: bool,
+    pub residual_mass: f64,
+}
+
+/// The kill order chosen for a room; `order` holds indices into `Room.monsters`.
+/// Room times are in ticks: the expected or quantile time being minimised.
+#[derive(Serialize, Clone, Debug)]
+pub struct KillOrderResult {
+    pub order: Vec<usize>,
+    pub room_ticks: f64,
+    pub listed_order_ticks: f64,
+    pub orders_evaluated: usize,
+    /// False when no order met every window; `order` is then the fastest overall.
+    pub feasible: bool,
+    pub windows: Vec<KillWindowResult>,
+}
+
+/// P(every monster in the group dies within `within_ticks` of the first) for the chosen order.
+#[derive(Serialize, Clone, Debug)]
+pub struct KillWindowResult {
+    pub monsters: Vec<usize>,
+    pub within_ticks: usize,
+    pub probability: f64,
+    pub met: bool,
+}
+
+/// An area-of-effect attack weighed against killing its targets one at a time.
+/// Times are expected ticks from the first attack until every target is dead.
+#[derive(Serialize, Clone, Debug)]
+pub struct AoeResult {
+    pub attack: String,
+    /// Indices into `Room.monsters`, at most the attack's target cap.
+    pub targets: Vec<usize>,
+    /// Expected casts until every target is dead; each cast hits them all.
+    pub expected_casts: f64,
+    pub aoe_ticks: f64,
+    pub single_target_ticks: f64,
+    /// Whether the room was simulated with the AoE attack.
+    pub chosen: bool,
+}
+
+/// One party member's part in a kill; `player` indexes the party, lead player first.
+#[derive(Serialize, Clone)]
+pub struct DamageShare {
+    pub player: usize,
+    pub expected_attacks: f64,
+    pub expected_damage: f64,
+    pub share: f64,
+}
+
+#[derive(Serialize, Clone, Default)]
+pub struct SupplyUsage {
+    pub prayer_points_drained: f64,
+    pub brew_doses: f64,
+    pub restore_doses: f64,
+    pub overload_doses: f64,
+    pub p_out_of_brews: f64,
+    pub p_out_of_restores: f64,
+    pub p_out_of_overloads: f64,
+}
+
+/// What one room (or a whole raid) asks of the inventory, as distributions
+/// that add up across rooms: `damage_pmf[d]` = P(d damage taken),
+/// `drain_pmf[k]` = P(k prayer points drained), `ticks_pmf[t]` = P(t ticks).
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct SupplyNeeds {
+    pub damage_pmf: Vec<f64>,
+    pub drain_pmf: Vec<f64>,
+    pub ticks_pmf: Vec<f64>,
+}
+
+/// Rooms of one raid, in order, for the player who will drink the supplies.
+#[derive(Deserialize)]
+pub struct RaidSuppliesPayload {
+    pub player: Player,
+    /// The `supply_needs` reported by each room.
+    pub rooms: Vec<SupplyNeeds>,
 }
 
 #[derive(Deserialize)]
@@ -206,11 +402,113 @@ pub struct DPSPayload {
 #[derive(Deserialize)]
 pub struct DPSConfig {
     pub cap: f64,
+    /// "melee", "ranged" or "magic"; attacks of that style are fully blocked.
+    #[serde(default)]
+    pub protection_prayer: Option<String>,
+    /// Prayers kept up for the whole room; derived from the combat style when absent.
+    #[serde(default)]
+    pub active_prayers: Option<Vec<String>>,
+    /// Tick target used to report P(kill time <= target).
+    #[serde(default)]
+    pub target_ticks: Option<usize>,
+    /// Extra quantiles (0-1) to report alongside p10/p50/p90/p99.
+    #[serde(default)]
+    pub quantiles: Vec<f64>,
+    /// Tick budget for each kill-time distribution; 1000 attacks when absent.
+    #[serde(default)]
+    pub max_ticks: Option<usize>,
+    /// Tick (from the first attack on each monster) to report the full remaining-HP distribution at.
+    #[serde(default)]
+    pub hp_distribution_tick: Option<usize>,
+    /// Thrall summoned for the room, if any.
+    #[serde(default)]
+    pub thrall: Option<ThrallConfig>,
+    /// Ticks after a kill before the next monster can be attacked; the room's own value when absent.
+    #[serde(default)]
+    pub retarget_delay: Option<usize>,
+    /// Search for the best kill order instead of killing monsters in listed order.
+    #[serde(default)]
+    pub kill_order: Option<KillOrderConfig>,
+    /// Consider an area-of-effect attack on stacked monsters.
+    #[serde(default)]
+    pub aoe: Option<AoeConfig>,
+    /// Vengeance cast by the lead player on cooldown.
+    #[serde(default)]
+    pub vengeance: Option<VengeanceConfig>,
+    /// Special attacks the lead player opens every fight with.
+    #[serde(default)]
+    pub spec: Option<SpecConfig>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct SpecConfig {
+    /// Gear set ("melee", "ranged" or "magic") whose weapon specs; the set fought with when absent.
+    #[serde(default)]
+    pub combat_type: Option<String>,
+    /// Special attack energy spent on each fight, out of 100; defaults to 100.
+    #[serde(default)]
+    pub energy: Option<u32>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct VengeanceConfig {
+    /// Tick of the first cast, counted from the room start.
+    #[serde(default)]
+    pub cast_tick: usize,
+}
+
+/// Area-of-effect options for a solo room; ignored when the party has teammates.
+#[derive(Deserialize, Clone)]
+pub struct AoeConfig {
+    /// e.g. "Ice barrage"; a chinchompa on the ranged weapon is used when absent.
+    #[serde(default)]
+    pub spell: Option<String>,
+    /// Indices into `Room.monsters` stacked for the attack; every monster when empty.
+    #[serde(default)]
+    pub targets: Vec<usize>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct KillOrderConfig {
+    /// Quantile (0-1) of room time to minimise; the expected room time when absent.
+    #[serde(default)]
+    pub quantile: Option<f64>,
+    /// Groups of monsters that must die close together.
+    #[serde(default)]
+    pub windows: Vec<KillWindowConfig>,
+}
+
+/// e.g. "both guardians must die within 10 ticks of each other".
+#[derive(Deserialize, Clone)]
+pub struct KillWindowConfig {
+    /// Indices into `Room.monsters`; every monster when empty.
+    #[serde(default)]
+    pub monsters: Vec<usize>,
+    /// Most ticks allowed between the first and the last death in the group.
+    pub within_ticks: usize,
+    /// Probability the window must be met with; defaults to 0.9.
+    #[serde(default)]
+    pub probability: Option<f64>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct ThrallConfig {
+    /// e.g. "Greater ghost", "Superior skeleton", "Lesser zombie".
+    pub name: String,
+    /// Tick the thrall is first cast, counted from the room start.
+    #[serde(default)]
+    pub cast_tick: usize,
+    /// Recast as soon as the thrall expires; defaults to true.
+    #[serde(default)]
+    pub recast: Option<bool>,
 }
 
 #[derive(Deserialize)]
 pub struct DPSRoomPayload {
     pub player: Player,
+    /// Other players in the room; the party is `player` followed by these.
+    #[serde(default)]
+    pub teammates: Vec<Player>,
     pub room: Room,
     pub config: DPSConfig,
 }
@@ -222,6 +520,8 @@ pub struct Room {
     pub image: Option<String>,
     pub description: Option<String>,
     pub monsters: Vec<Monster>,
+    #[serde(default)]
+    pub distance: Option<u32>,
 }
 
 use serde::de::{self, Deserializer};
@@ -1,5 +1,7 @@
 use wasm_bindgen::prelude::*;
 use serde::{Deserialize, Serialize};
+use osrs_shared_functions::{attack_speed_for, hit_delay_for, AttackSchedule, Attacker, Cdf, Heal, HitDistribution, Thrall, DEFAULT_ATTACK_SPEED, DEFAULT_DISTANCE};
+use osrs_shared_types::{MonsterHeal, ThrallConfig};
 
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
 // allocator.
@@ -95,6 +97,10 @@ pub struct SelectedWeapon {
     pub name: String,
     #[serde(deserialize_with = "from_str_or_int")]
     pub id: u32,
+    #[serde(default)]
+    pub speed: Option<i32>,
+    #[serde(default)]
+    pub category: String,
     #[serde(rename = "weapon_styles")]
     pub weapon_styles: Vec<WeaponStyle>,
 }
@@ -180,6 +186,12 @@ pub struct Monster {
     pub attributes: Option<Vec<String>>,
     pub immunities: Option<serde_json::Value>,
     pub weakness: Option<serde_json::Value>,
+    /// Crystal heals and the like, counted from the first attack.
+    #[serde(default)]
+    pub heals: Vec<MonsterHeal>,
+    /// Tiles between the player and the monster; `DEFAULT_DISTANCE` when absent.
+    #[serde(default)]
+    pub distance: Option<u32>,
 }
 
 #[derive(Serialize)]
@@ -251,6 +263,7 @@ pub struct StyleResult {
     pub effective_attack: u32,
     pub max_attack_roll: u64,
     pub max_defence_roll: u64,
+    pub attack_speed: usize,
 }
 
 fn find_best_combat_style(player: &Player, monster: &Monster) -> StyleResult {
@@ -262,7 +275,9 @@ fn find_best_combat_style(player: &Player, monster: &Monster) -> StyleResult {
         for style in &weapon.weapon_styles {
             let (max_hit, effective_strength) = calculate_max_hit_for_style(player, style, &player.gear_sets.melee.gear_stats);
             let (accuracy, effective_attack, max_attack_roll, max_defence_roll) = calculate_accuracy_for_style(player, monster, style, &player.gear_sets.melee.gear_stats);
-            let effective_dps = max_hit as f64 * accuracy;
+            let speed = weapon.speed.unwrap_or(DEFAULT_ATTACK_SPEED as i32);
+            let attack_speed = attack_speed_for(&weapon.name, &weapon.category, speed, "melee", style.att_spd_reduction);
+            let effective_dps = max_hit as f64 * accuracy / attack_speed as f64;
             console_log!("Style: {} ({}), Max Hit: {}, Accuracy: {:.2}%, Effective DPS: {:.2}", style.combat_style, style.attack_type, max_hit, accuracy * 100.0, effective_dps);
             let style_result = StyleResult {
                 combat_style: style.combat_style.clone(),
@@ -274,6 +289,7 @@ fn find_best_combat_style(player: &Player, monster: &Monster) -> StyleResult {
                 effective_attack,
                 max_attack_roll,
                 max_defence_roll,
+                attack_speed,
             };
             if effective_dps > best_dps {
                 best_dps = effective_dps;
@@ -289,8 +305,8 @@ fn find_best_combat_style(player: &Player, monster: &Monster) -> StyleResult {
         let potion_bonus = 19;
         let prayer_strength_bonus = 1.23;
         let prayer_attack_bonus = 1.20;
-        let effective_strength = ((((strength_level + potion_bonus) as f64 * prayer_strength_bonus).floor() + 8.0)).floor() as u32;
-        let effective_attack = ((((attack_level + potion_bonus) as f64 * prayer_attack_bonus).floor() + 8.0)).floor() as u32;
+        let effective_strength = (((strength_level + potion_bonus) as f64 * prayer_strength_bonus).floor() + 8.0).floor() as u32;
+        let effective_attack = (((attack_level + potion_bonus) as f64 * prayer_attack_bonus).floor() + 8.0).floor() as u32;
         let strength_bonus = player.gear_sets.melee.gear_stats.bonuses.str;
         let attack_bonus = player.gear_sets.melee.gear_stats.offensive.stab;
         let max_hit = (0.5 + (effective_strength as f64 * (strength_bonus + 64) as f64) / 640.0).floor() as u32;
@@ -306,11 +322,12 @@ fn find_best_combat_style(player: &Player, monster: &Monster) -> StyleResult {
             attack_type: "stab".to_string(),
             max_hit,
             accuracy,
-            effective_dps: max_hit as f64 * accuracy,
+            effective_dps: max_hit as f64 * accuracy / 5.0,
             effective_strength,
             effective_attack,
             max_attack_roll,
             max_defence_roll,
+            attack_speed: 5,
         });
     }
     let result = best_style.unwrap();
@@ -359,7 +376,28 @@ pub fn calculate_dps_with_objects_vasa(payload_json: &str) -> String {
     console_log!("Using best melee style - Max Hit: {}, Accuracy: {:.2}%", best_style.max_hit, best_style.accuracy * 100.0);
     
     // Generate kill time distribution using the best style
-    let kill_times = weapon_and_thrall_kill_times_internal(monster.skills.hp as usize, best_style.max_hit as usize, best_style.accuracy, cap);
+    let thrall = match thrall_from_config(payload.config.thrall.as_ref(), player.combat_stats.magic) {
+        Ok(thrall) => Some(thrall),
+        Err(e) => return format!("{{\"error\": \"Failed to parse payload data: {}\"}}", e),
+    };
+    let max_ticks = payload.config.max_ticks.unwrap_or(MAX_KILL_TICKS);
+    let hp = monster.skills.hp as usize;
+    let distance = monster.distance.unwrap_or(DEFAULT_DISTANCE);
+    let hit_delay = player.gear_sets.melee.selected_weapon.as_ref()
+        .map_or(MELEE_HIT_DELAY, |weapon| hit_delay_for("melee", &weapon.name, &weapon.category, distance));
+    let schedule = weapon_and_thrall_schedule(best_style.max_hit as usize, best_style.accuracy, best_style.attack_speed, hit_delay, thrall.as_ref(), &monster.heals, max_ticks);
+    let run = schedule.run(hp, cap, max_ticks);
+    // Ticks are reported from tick 1, the weapon's first attack
+    let kill_times = run.cdf.probs.get(1..).unwrap_or_default().to_vec();
+    let hp_bands = run.hp_bands.get(1..).unwrap_or_default().to_vec();
+    let hp_distribution = payload.config.hp_distribution_tick.map(|tick| schedule.hp_distribution_at(hp, tick));
+    let killable = can_damage(best_style.accuracy, best_style.max_hit as usize) || thrall.is_some();
+    let convergence = Cdf::new(kill_times.clone()).convergence(cap, killable);
+    // Summarised over the tick-0-based CDF so quantiles and the target are in ticks
+    let kill_time_summary = run.cdf.summary(payload.config.target_ticks, &payload.config.quantiles);
+    if !killable {
+        console_log!("Vasa cannot be killed with the selected gear");
+    }
     
     let result = CalculationResult {
         max_hit: best_style.max_hit,
@@ -378,7 +416,11 @@ pub fn calculate_dps_with_objects_vasa(payload_json: &str) -> String {
             "effective_dps": best_style.effective_dps,
             "gear_type": "melee"
         },
-        "kill_times": kill_times
+        "kill_times": kill_times,
+        "kill_time_summary": kill_time_summary,
+        "convergence": convergence,
+        "hp_bands": hp_bands,
+        "hp_distribution": hp_distribution
     });
     
     response.to_string()
@@ -388,8 +430,7 @@ pub fn calculate_dps_with_objects_vasa(payload_json: &str) -> String {
 fn row_vec_times_square_mat(row: &[f64], mat: &[f64], n: usize) -> Vec<f64> {
     let mut out = vec![0.0; n];
     // out[j] = sum_i row[i] * mat[i,j]
-    for i in 0..n {
-        let r = row[i];
+    for (i, &r) in row.iter().enumerate().take(n) {
         if r == 0.0 { continue; }
         let row_start = i * n;
         for j in 0..n {
@@ -448,8 +489,15 @@ fn hitting_basic_npc_internal(hp: usize, max_hit: usize, acc: f64, no_hits: usiz
     state
 }
 
-/// Distribution of P(dead) by number of hits, until cap is reached.
-/// Returns a Vec of cumulative death probabilities per hit index (0-based: after i hits).
+/// Whether a weapon with accuracy `acc` and max hit `max_hit` can ever do damage.
+fn can_damage(acc: f64, max_hit: usize) -> bool {
+    acc > 0.0 && max_hit > 0
+}
+
+/// Distribution of P(dead) by number of hits, until cap is reached or after
+/// `MAX_HITS` hits. Returns a Vec of cumulative death probabilities per hit
+/// index (0-based: after i hits); just the starting state if the weapon can
+/// never do damage.
 fn distribution_of_hits_to_kill_internal(hp: usize, max_hit: usize, acc: f64, cap: f64) -> Vec<f64> {
     let n = hp + 1;
     let t = single_matrix_internal(acc, max_hit, hp);
@@ -458,8 +506,11 @@ fn distribution_of_hits_to_kill_internal(hp: usize, max_hit: usize, acc: f64, ca
     let mut out = Vec::with_capacity(128);
     let mut p_dead = state[n - 1];
     out.push(p_dead);
+    if hp > 0 && !can_damage(acc, max_hit) {
+        return out;
+    }
 
-    while p_dead < cap {
+    while p_dead < cap && out.len() <= MAX_HITS {
         state = row_vec_times_square_mat(&state, &t, n);
         p_dead = state[n - 1];
         out.push(p_dead);
@@ -468,41 +519,56 @@ fn distribution_of_hits_to_kill_internal(hp: usize, max_hit: usize, acc: f64, ca
     out
 }
 
-/// Thrall matrix: accuracy 0.75, max hit 3
-fn thrall_matrix_internal(hp: usize) -> Vec<f64> {
-    single_matrix_internal(0.75, 3, hp)
-}
+/// Ticks simulated before giving up on reaching the cap.
+const MAX_KILL_TICKS: usize = 10_000;
 
-/// Weapon+Thrall tick simulation:
-/// - weapon hits every 5th tick, starting at tick 1 (ticks: 1,6,11,...)
-/// - thrall hits every 4th tick, starting at tick 2 (ticks: 2,6,10,...)
-/// Returns cumulative P(dead) per tick, until cap hit.
-fn weapon_and_thrall_kill_times_internal(hp: usize, max_hit: usize, acc: f64, cap: f64) -> Vec<f64> {
-    let n = hp + 1;
-    let weapon = single_matrix_internal(acc, max_hit, hp);
-    let thrall = thrall_matrix_internal(hp);
-    let mut state = npc_state_internal(hp);
+/// Hits simulated by `distribution_of_hits_to_kill` before giving up on reaching the cap.
+const MAX_HITS: usize = 1000;
 
-    let mut out = Vec::with_capacity(512);
-    let mut p_dead = state[n - 1];
-    let mut tick: usize = 0;
+/// Ticks from a melee attack to its hitsplat.
+const MELEE_HIT_DELAY: usize = 1;
 
-    while p_dead < cap {
-        tick += 1;
+/// Thrall assumed when the payload does not name one.
+const DEFAULT_THRALL: &str = "Greater ghost";
 
-        // weapon on ticks 1,6,11,... => tick % 5 == 1
-        if tick % 5 == 1 {
-            state = row_vec_times_square_mat(&state, &weapon, n);
-        }
-        // thrall on ticks 2,6,10,... => tick % 4 == 2
-        if tick % 4 == 2 {
-            state = row_vec_times_square_mat(&state, &thrall, n);
-        }
+/// Magic level assumed by the bare `weapon_and_thrall_kill_times` export.
+const DEFAULT_MAGIC_LEVEL: u32 = 99;
 
-        p_dead = state[n - 1];
-        out.push(p_dead);
+fn thrall_from_config(config: Option<&ThrallConfig>, magic_level: u32) -> Result<Thrall, String> {
+    let default = ThrallConfig { name: DEFAULT_THRALL.to_string(), cast_tick: 0, recast: None };
+    Thrall::from_config(config.unwrap_or(&default), magic_level)
+}
+
+/// Per-hit damage matching `single_matrix_internal`: uniform on [1, m] on a hit.
+fn single_hit_distribution(a: f64, m: usize) -> HitDistribution {
+    if m == 0 {
+        return HitDistribution::from_pmf(vec![1.0]);
     }
-    out
+    let mut pmf = vec![a / m as f64; m + 1];
+    pmf[0] = 1.0 - a;
+    HitDistribution::from_pmf(pmf)
+}
+
+/// Weapon+Thrall tick schedule:
+/// - weapon attacks every `attack_speed` ticks, starting at tick 1 (ticks: 1,6,11,... at 5 ticks),
+///   each hitsplat landing `hit_delay` ticks later
+/// - thrall hits every 4 ticks from 2 ticks after its cast (ticks: 2,6,10,... when cast on tick 0)
+///   until it expires
+/// - the monster heals as listed in `heals`, after any hitsplat on the same tick
+fn weapon_and_thrall_schedule(max_hit: usize, acc: f64, attack_speed: usize, hit_delay: usize, thrall: Option<&Thrall>, heals: &[MonsterHeal], max_ticks: usize) -> AttackSchedule {
+    let weapon = Attacker::new("weapon", single_hit_distribution(acc, max_hit), attack_speed)
+        .with_first_attack_tick(1)
+        .with_hit_delay(hit_delay);
+    let thralls = thrall.map(|t| t.attackers(0, max_ticks)).unwrap_or_default();
+    let schedule = thralls.into_iter().fold(AttackSchedule::new().with_attacker(weapon), AttackSchedule::with_attacker);
+    heals.iter().map(Heal::from_monster_heal).fold(schedule, AttackSchedule::with_heal)
+}
+
+/// Returns cumulative P(dead) per tick from tick 1, until cap hit or `max_ticks`.
+fn weapon_and_thrall_kill_times_internal(hp: usize, max_hit: usize, acc: f64, cap: f64, attack_speed: usize, thrall: Option<&Thrall>) -> Vec<f64> {
+    let schedule = weapon_and_thrall_schedule(max_hit, acc, attack_speed, MELEE_HIT_DELAY, thrall, &[], MAX_KILL_TICKS);
+    let kill_cdf = schedule.kill_cdf(hp, cap, MAX_KILL_TICKS);
+    kill_cdf.probs.get(1..).unwrap_or_default().to_vec()
 }
 
 // Keep existing functions for backward compatibility
@@ -528,7 +594,8 @@ pub fn distribution_of_hits_to_kill(hp: usize, max_hit: usize, acc: f64, cap: f6
 
 #[wasm_bindgen]
 pub fn weapon_and_thrall_kill_times(hp: usize, max_hit: usize, acc: f64, cap: f64) -> Vec<f64> {
-    weapon_and_thrall_kill_times_internal(hp, max_hit, acc, cap)
+    let thrall = thrall_from_config(None, DEFAULT_MAGIC_LEVEL).ok();
+    weapon_and_thrall_kill_times_internal(hp, max_hit, acc, cap, 5, thrall.as_ref())
 }
 
 #[derive(Deserialize)]
@@ -541,6 +608,21 @@ pub struct DPSPayload {
 #[derive(Deserialize)]
 pub struct DPSConfig {
     pub cap: f64,
+    /// Thrall kept up during the fight; a greater ghost when absent.
+    #[serde(default)]
+    pub thrall: Option<ThrallConfig>,
+    /// Tick budget for the kill-time distribution; 10,000 ticks when absent.
+    #[serde(default)]
+    pub max_ticks: Option<usize>,
+    /// Tick to report the full remaining-HP distribution at.
+    #[serde(default)]
+    pub hp_distribution_tick: Option<usize>,
+    /// Tick target used to report P(kill time <= target).
+    #[serde(default)]
+    pub target_ticks: Option<usize>,
+    /// Extra quantiles (0-1) to report alongside p10/p50/p90/p99.
+    #[serde(default)]
+    pub quantiles: Vec<f64>,
 }
 // Custom deserializer for u32 that accepts string or int
 use serde::de::{self, Deserializer};